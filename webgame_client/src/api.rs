@@ -1,10 +1,14 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use yew::agent::{Agent, AgentLink, Context, HandlerId};
-use yew::format::Json;
+use yew::format::{Binary, Json, Text};
 use yew::services::websocket::{WebSocketService, WebSocketStatus, WebSocketTask};
+use yew::Callback;
 
-use crate::protocol::{Command, Message};
+use crate::protocol::{
+    Command, CommandEnvelope, Encoding, ErrorCode, HelloCommand, Message, ProtocolError,
+    PROTOCOL_VERSION,
+};
 
 #[derive(Debug)]
 pub enum ApiState {
@@ -20,6 +24,58 @@ pub enum Msg {
     Ignore,
 }
 
+/// A command to send to the server.
+///
+/// Errors caused by a command are only passed to the component that sent
+/// it.  If a callback is attached it is invoked with the outcome instead.
+pub struct Request {
+    command: Command,
+    on_response: Option<Callback<Result<(), ProtocolError>>>,
+}
+
+impl Request {
+    pub fn new(command: Command) -> Request {
+        Request {
+            command,
+            on_response: None,
+        }
+    }
+
+    /// Sets a callback invoked once the server handled the command.
+    pub fn on_response(mut self, callback: Callback<Result<(), ProtocolError>>) -> Request {
+        self.on_response = Some(callback);
+        self
+    }
+}
+
+impl From<Command> for Request {
+    fn from(command: Command) -> Request {
+        Request::new(command)
+    }
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    handler: HandlerId,
+    on_response: Option<Callback<Result<(), ProtocolError>>>,
+}
+
+/// A message from the server decoded from a text or a binary frame.
+struct Incoming(Result<Message, anyhow::Error>);
+
+impl From<Text> for Incoming {
+    fn from(text: Text) -> Incoming {
+        let Json(message) = Json::from(text);
+        Incoming(message)
+    }
+}
+
+impl From<Binary> for Incoming {
+    fn from(binary: Binary) -> Incoming {
+        Incoming(binary.and_then(|bytes| rmp_serde::from_read_ref(&bytes).map_err(Into::into)))
+    }
+}
+
 #[derive(Debug)]
 pub struct Api {
     link: AgentLink<Api>,
@@ -27,6 +83,22 @@ pub struct Api {
     ws_service: WebSocketService,
     subscribers: HashSet<HandlerId>,
     state: ApiState,
+    encoding: Encoding,
+    next_request_id: u64,
+    pending: HashMap<u64, PendingRequest>,
+}
+
+/// The encodings offered to the server.
+///
+/// Debug builds stick to JSON so the traffic can be read in the browser's
+/// developer tools.
+fn preferred_encodings() -> Vec<String> {
+    let encodings = if cfg!(debug_assertions) {
+        vec![Encoding::Json]
+    } else {
+        vec![Encoding::Msgpack, Encoding::Json]
+    };
+    encodings.into_iter().map(|x| x.name().into()).collect()
 }
 
 fn get_websocket_location() -> String {
@@ -45,12 +117,12 @@ fn get_websocket_location() -> String {
 impl Agent for Api {
     type Reach = Context;
     type Message = Msg;
-    type Input = Command;
+    type Input = Request;
     type Output = Message;
 
     fn create(link: AgentLink<Api>) -> Api {
         log::info!("Connecting to server");
-        let on_message = link.callback(|Json(data)| match data {
+        let on_message = link.callback(|Incoming(data)| match data {
             Ok(message) => Msg::ServerMessage(message),
             Err(err) => {
                 log::error!("websocket error: {:?}", err);
@@ -72,18 +144,63 @@ impl Agent for Api {
             ws_service,
             state: ApiState::Connecting,
             subscribers: HashSet::new(),
+            encoding: Encoding::Json,
+            next_request_id: 1,
+            pending: HashMap::new(),
         }
     }
 
-    fn handle_input(&mut self, input: Self::Input, _: HandlerId) {
-        log::debug!("Sending command: {:?}", &input);
-        self.ws.send(Json(&input));
+    fn handle_input(&mut self, input: Self::Input, who: HandlerId) {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        self.pending.insert(
+            request_id,
+            PendingRequest {
+                handler: who,
+                on_response: input.on_response,
+            },
+        );
+        let envelope = CommandEnvelope {
+            request_id: Some(request_id),
+            command: input.command,
+        };
+        log::debug!("Sending command: {:?}", &envelope);
+        match self.encoding {
+            Encoding::Json => self.ws.send(Json(&envelope)),
+            Encoding::Msgpack => self
+                .ws
+                .send_binary(rmp_serde::to_vec_named(&envelope).map_err(Into::into)),
+        }
     }
 
     fn update(&mut self, msg: Self::Message) {
         match msg {
             Msg::ServerMessage(msg) => {
                 log::debug!("Server message: {:?}", msg);
+                match msg {
+                    Message::Hello(ref hello) => {
+                        self.encoding = hello.encoding;
+                    }
+                    Message::Ack(ref ack) => {
+                        if let Some(request) = self.pending.remove(&ack.request_id) {
+                            if let Some(callback) = request.on_response {
+                                callback.emit(Ok(()));
+                            }
+                        }
+                        return;
+                    }
+                    Message::Error(ref err) => {
+                        let request = err.request_id().and_then(|id| self.pending.remove(&id));
+                        if let Some(request) = request {
+                            match request.on_response {
+                                Some(callback) => callback.emit(Err(err.clone())),
+                                None => self.link.respond(request.handler, msg.clone()),
+                            }
+                            return;
+                        }
+                    }
+                    _ => {}
+                }
                 for sub in self.subscribers.iter() {
                     self.link.respond(*sub, msg.clone());
                 }
@@ -91,10 +208,23 @@ impl Agent for Api {
             Msg::Connected => {
                 log::info!("Connected web socket!");
                 self.state = ApiState::Connected;
+                self.ws.send(Json(&Command::Hello(HelloCommand {
+                    protocol_version: PROTOCOL_VERSION,
+                    client_name: concat!("webgame_client/", env!("CARGO_PKG_VERSION")).into(),
+                    encodings: preferred_encodings(),
+                })));
             }
             Msg::ConnectionLost => {
                 log::info!("Lost connection on web socket!");
                 self.state = ApiState::Disconnected;
+                for (_, request) in self.pending.drain() {
+                    if let Some(callback) = request.on_response {
+                        callback.emit(Err(ProtocolError::new(
+                            ErrorCode::ConnectionLost,
+                            "lost connection to the server",
+                        )));
+                    }
+                }
             }
             Msg::Ignore => {}
         }
@@ -106,6 +236,7 @@ impl Agent for Api {
 
     fn disconnected(&mut self, id: HandlerId) {
         self.subscribers.remove(&id);
+        self.pending.retain(|_, request| request.handler != id);
     }
 
     fn destroy(&mut self) {
@@ -1,24 +1,34 @@
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
 use yew::agent::Bridged;
 use yew::{html, Bridge, Component, ComponentLink, Html, ShouldRender};
 
 use crate::api::Api;
-use crate::protocol::{GameInfo, Message, PlayerInfo};
+use crate::i18n::Language;
+use crate::protocol::{Command, GameInfo, Message, PlayerInfo};
+use crate::router::Route;
 use crate::views::game::{GamePage, GamePageCommand};
 use crate::views::menu::MenuPage;
 use crate::views::start::StartPage;
+use crate::views::stats::StatsPage;
 
 pub struct App {
-    _api: Box<dyn Bridge<Api>>,
+    api: Box<dyn Bridge<Api>>,
     link: ComponentLink<Self>,
+    _on_popstate: Closure<dyn Fn()>,
     state: AppState,
     player_info: Option<PlayerInfo>,
     game_info: Option<GameInfo>,
+    join_code: Option<String>,
+    features: Vec<String>,
+    language: Language,
 }
 
 #[derive(Debug)]
 enum AppState {
     Start,
     Authenticated,
+    Stats,
     InGame,
 }
 
@@ -26,7 +36,40 @@ pub enum Msg {
     Authenticated(PlayerInfo),
     GameJoined(GameInfo),
     GamePageCommand(GamePageCommand),
+    ShowStats,
+    HideStats,
     ServerMessage(Message),
+    SetLanguage(Language),
+    RouteChanged(Route),
+}
+
+impl App {
+    /// Checks if the server announced an optional feature.
+    fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|x| x == feature)
+    }
+
+    /// Follows the back and forward buttons of the browser.
+    fn follow_route(&mut self, route: Route) {
+        if self.player_info.is_none() {
+            self.join_code = route.join_code().map(|x| x.to_string());
+            return;
+        }
+        let current_join_code = self.game_info.as_ref().map(|x| x.join_code.as_str());
+        if route.join_code().is_some() && route.join_code() == current_join_code {
+            return;
+        }
+        self.join_code = route.join_code().map(|x| x.to_string());
+        match self.state {
+            // the menu is shown and joins once the game is left
+            AppState::InGame => self.api.send(Command::LeaveGame.into()),
+            AppState::Stats | AppState::Authenticated => self.state = AppState::Authenticated,
+            AppState::Start => {}
+        }
+        if route == Route::Start {
+            Route::Games.replace();
+        }
+    }
 }
 
 impl Component for App {
@@ -35,12 +78,25 @@ impl Component for App {
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
         let on_server_message = link.callback(Msg::ServerMessage);
+        let on_route_changed = link.callback(Msg::RouteChanged);
+        let on_popstate = Closure::wrap(
+            Box::new(move || on_route_changed.emit(Route::current())) as Box<dyn Fn()>
+        );
+        if let Some(window) = web_sys::window() {
+            window
+                .add_event_listener_with_callback("popstate", on_popstate.as_ref().unchecked_ref())
+                .ok();
+        }
         App {
             link,
-            _api: Api::bridge(on_server_message),
+            api: Api::bridge(on_server_message),
+            _on_popstate: on_popstate,
             state: AppState::Start,
             player_info: None,
             game_info: None,
+            join_code: Route::current().join_code().map(|x| x.to_string()),
+            features: vec![],
+            language: Language::detect(),
         }
     }
 
@@ -49,16 +105,38 @@ impl Component for App {
             Msg::Authenticated(player_info) => {
                 self.state = AppState::Authenticated;
                 self.player_info = Some(player_info);
+                Route::Games.replace();
             }
             Msg::GameJoined(game_info) => {
                 self.state = AppState::InGame;
+                Route::Game(game_info.join_code.clone()).push();
                 self.game_info = Some(game_info);
+                self.join_code = None;
             }
             Msg::ServerMessage(Message::GameLeft) | Msg::GamePageCommand(GamePageCommand::Quit) => {
                 self.state = AppState::Authenticated;
-                self.game_info = None;
+                // games left by following the back and forward buttons
+                // already moved on to another route.
+                let game_route = self.game_info.take().map(|x| Route::Game(x.join_code));
+                if game_route == Some(Route::current()) {
+                    Route::Games.push();
+                }
+            }
+            Msg::ShowStats => {
+                self.state = AppState::Stats;
+            }
+            Msg::HideStats => {
+                self.state = AppState::Authenticated;
+            }
+            Msg::ServerMessage(Message::Hello(hello)) => {
+                self.features = hello.features;
             }
             Msg::ServerMessage(_) => {}
+            Msg::SetLanguage(language) => {
+                language.store();
+                self.language = language;
+            }
+            Msg::RouteChanged(route) => self.follow_route(route),
         }
         true
     }
@@ -68,20 +146,43 @@ impl Component for App {
             <div class="game">
             {match self.state {
                 AppState::Start => html! {
-                    <StartPage on_authenticate=self.link.callback(Msg::Authenticated) />
+                    <StartPage
+                        language=self.language,
+                        on_authenticate=self.link.callback(Msg::Authenticated) />
                 },
                 AppState::Authenticated => html! {
                     <MenuPage
+                        language=self.language,
                         player_info=self.player_info.as_ref().unwrap().clone(),
-                        on_game_joined=self.link.callback(Msg::GameJoined) />
+                        stats_enabled=self.has_feature("stats"),
+                        join_code=self.join_code.clone(),
+                        on_game_joined=self.link.callback(Msg::GameJoined),
+                        on_show_stats=self.link.callback(|_| Msg::ShowStats) />
+                },
+                AppState::Stats => html! {
+                    <StatsPage
+                        language=self.language,
+                        on_back=self.link.callback(|_| Msg::HideStats) />
                 },
                 AppState::InGame => html! {
                     <GamePage
+                        language=self.language,
                         player_info=self.player_info.as_ref().unwrap().clone(),
                         game_info=self.game_info.as_ref().unwrap().clone(),
+                        rematch_enabled=self.has_feature("rematch"),
                         on_game_command=self.link.callback(Msg::GamePageCommand) />
                 }
             }}
+            <div class="toolbar language">
+                <span>{self.language.strings().language}</span>
+                {for Language::ALL.iter().map(|&language| html! {
+                    <button
+                        disabled=language == self.language
+                        onclick=self.link.callback(move |_| Msg::SetLanguage(language))>
+                        {language.native_name()}
+                    </button>
+                })}
+            </div>
             </div>
         }
     }
@@ -4,11 +4,15 @@ use im_rc::Vector;
 use web_sys::Element;
 use yew::{html, Component, ComponentLink, Html, NodeRef, Properties, ShouldRender};
 
+use crate::i18n::Language;
+use crate::protocol::ChatChannel;
+
 #[derive(PartialEq)]
 pub enum ChatLineData {
     Connected,
     Disconnected,
-    Text(String),
+    Text(ChatChannel, String),
+    Notice(String),
 }
 
 #[derive(PartialEq)]
@@ -19,26 +23,50 @@ pub struct ChatLine {
 
 #[derive(Clone, Properties)]
 pub struct Props {
+    pub language: Language,
     pub log: Vector<Rc<ChatLine>>,
 }
 
 pub struct ChatBox {
+    language: Language,
     log: Vector<Rc<ChatLine>>,
     link: ComponentLink<ChatBox>,
     log_ref: NodeRef,
 }
 
 impl ChatLine {
-    pub fn text(&self) -> &str {
+    pub fn text(&self, language: Language) -> &str {
+        match self.data {
+            ChatLineData::Connected => language.strings().chat_connected,
+            ChatLineData::Disconnected => language.strings().chat_disconnected,
+            ChatLineData::Text(_, ref x) | ChatLineData::Notice(ref x) => x.as_str(),
+        }
+    }
+
+    pub fn is_notice(&self) -> bool {
+        matches!(self.data, ChatLineData::Notice(..))
+    }
+
+    pub fn channel(&self) -> ChatChannel {
         match self.data {
-            ChatLineData::Connected => "*connected*",
-            ChatLineData::Disconnected => "*disconnected*",
-            ChatLineData::Text(ref x) => x.as_str(),
+            ChatLineData::Text(channel, _) => channel,
+            _ => ChatChannel::All,
         }
     }
 
-    pub fn render(&self) -> String {
-        format!("<{}> {}", self.nickname, self.text())
+    pub fn render(&self, language: Language) -> String {
+        let text = self.text(language);
+        if self.is_notice() {
+            return format!("*** {}", text);
+        }
+        let s = language.strings();
+        match self.channel() {
+            ChatChannel::All => format!("<{}> {}", self.nickname, text),
+            ChatChannel::Team => format!("{} <{}> {}", s.chat_team, self.nickname, text),
+            ChatChannel::Spectators => {
+                format!("{} <{}> {}", s.chat_spectators, self.nickname, text)
+            }
+        }
     }
 }
 
@@ -48,6 +76,7 @@ impl Component for ChatBox {
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         ChatBox {
+            language: props.language,
             log: props.log,
             link,
             log_ref: NodeRef::default(),
@@ -63,9 +92,13 @@ impl Component for ChatBox {
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
         if self.log != props.log {
+            self.language = props.language;
             self.log = props.log;
             self.link.send_message(());
             true
+        } else if self.language != props.language {
+            self.language = props.language;
+            true
         } else {
             false
         }
@@ -74,11 +107,16 @@ impl Component for ChatBox {
     fn view(&self) -> Html {
         html! {
             <div class="chat box">
-                <h2>{"Chat"}</h2>
+                <h2>{self.language.strings().chat}</h2>
                 <ul id="chat-log" ref=self.log_ref.clone()>
                 {
                     for self.log.iter().map(|item| html! {
-                        <li>{item.render()}</li>
+                        <li class={match item.channel() {
+                            ChatChannel::All if item.is_notice() => "notice",
+                            ChatChannel::All => "channel-all",
+                            ChatChannel::Team => "channel-team",
+                            ChatChannel::Spectators => "channel-spectators",
+                        }}>{item.render(self.language)}</li>
                     })
                 }
                 </ul>
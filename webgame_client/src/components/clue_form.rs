@@ -0,0 +1,189 @@
+use std::rc::Rc;
+
+use yew::{
+    html, Callback, Component, ComponentLink, Html, InputData, KeyboardEvent, Properties,
+    ShouldRender,
+};
+
+use crate::i18n::{fill, Language};
+use crate::protocol::{GameStateSnapshot, ShareCodenameCommand};
+
+/// The highest number offered by the picker.
+const MAX_NUMBER: usize = 9;
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub language: Language,
+    pub game_state: Rc<GameStateSnapshot>,
+    pub on_share: Callback<ShareCodenameCommand>,
+}
+
+/// Lets the active spymaster enter a codename and a number.
+pub struct ClueForm {
+    link: ComponentLink<ClueForm>,
+    language: Language,
+    game_state: Rc<GameStateSnapshot>,
+    on_share: Callback<ShareCodenameCommand>,
+    word: String,
+    number: Option<usize>,
+    confirming: bool,
+    error: Option<&'static str>,
+}
+
+pub enum Msg {
+    Ignore,
+    SetWord(String),
+    SetNumber(Option<usize>),
+    Submit,
+    Confirm,
+    Cancel,
+}
+
+impl ClueForm {
+    /// Checks the codename the same way the server does.
+    fn validate(&self) -> Result<String, &'static str> {
+        let s = self.language.strings();
+        let word = self.word.trim().to_lowercase();
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            return Err(s.error_codename_not_single_word);
+        }
+        if self
+            .game_state
+            .tiles
+            .iter()
+            .any(|tile| !tile.spotted && tile.codeword.to_lowercase() == word)
+        {
+            return Err(s.error_codename_on_board);
+        }
+        Ok(word)
+    }
+
+    fn clue_text(&self) -> String {
+        self.language.clue_text(
+            self.word.trim(),
+            self.number.unwrap_or(0),
+            self.number.is_none(),
+        )
+    }
+
+    fn number_button(&self, number: Option<usize>) -> Html {
+        let label = match number {
+            Some(number) => number.to_string(),
+            None => self.language.strings().clue_unlimited.to_string(),
+        };
+        html! {
+            <button
+                disabled=self.number == number
+                onclick=self.link.callback(move |_| Msg::SetNumber(number))>
+                {label}
+            </button>
+        }
+    }
+}
+
+impl Component for ClueForm {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        ClueForm {
+            link,
+            language: props.language,
+            game_state: props.game_state,
+            on_share: props.on_share,
+            word: "".into(),
+            number: Some(1),
+            confirming: false,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::SetWord(word) => {
+                self.word = word;
+                self.error = None;
+            }
+            Msg::SetNumber(number) => {
+                self.number = number;
+            }
+            Msg::Submit => match self.validate() {
+                Ok(_) => self.confirming = true,
+                Err(err) => self.error = Some(err),
+            },
+            Msg::Confirm => {
+                self.confirming = false;
+                match self.validate() {
+                    Ok(codename) => {
+                        self.on_share.emit(ShareCodenameCommand {
+                            codename,
+                            number: self.number.unwrap_or(0),
+                            unlimited: self.number.is_none(),
+                        });
+                        self.word.clear();
+                    }
+                    Err(err) => self.error = Some(err),
+                }
+            }
+            Msg::Cancel => {
+                self.confirming = false;
+            }
+            Msg::Ignore => return false,
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.game_state != props.game_state || self.language != props.language {
+            self.language = props.language;
+            self.game_state = props.game_state;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let s = self.language.strings();
+        html! {
+            <div class="clue-form">
+                {if self.confirming {
+                    html! {
+                        <div class="toolbar">
+                            <span>{fill(s.clue_confirm, &[&self.clue_text()])}</span>
+                            <button class="primary" onclick=self.link.callback(|_| Msg::Confirm)>{s.share_codename}</button>
+                            <button class="cancel" onclick=self.link.callback(|_| Msg::Cancel)>{s.cancel}</button>
+                        </div>
+                    }
+                } else {
+                    html! {
+                        <div class="toolbar">
+                            <input value=&self.word
+                                placeholder=s.clue_placeholder
+                                size="20"
+                                onkeypress=self.link.callback(|event: KeyboardEvent| {
+                                    if event.key() == "Enter" {
+                                        Msg::Submit
+                                    } else {
+                                        Msg::Ignore
+                                    }
+                                })
+                                oninput=self.link.callback(|e: InputData| Msg::SetWord(e.value)) />
+                            <span>{s.clue_number}</span>
+                            {for (0..=MAX_NUMBER).map(|number| self.number_button(Some(number)))}
+                            {self.number_button(None)}
+                            <button class="primary" onclick=self.link.callback(|_| Msg::Submit)>{s.share_codename}</button>
+                        </div>
+                    }
+                }}
+                {if let Some(error) = self.error {
+                    html! {
+                        <p class="error">{error}</p>
+                    }
+                } else {
+                    html! {}
+                }}
+            </div>
+        }
+    }
+}
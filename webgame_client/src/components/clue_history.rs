@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use yew::{html, Component, ComponentLink, Html, Properties, ShouldRender};
+
+use crate::i18n::{fill, Language};
+use crate::protocol::{Character, ClueLogEntry, GameStateSnapshot, Team};
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub language: Language,
+    pub game_state: Rc<GameStateSnapshot>,
+}
+
+pub struct ClueHistory {
+    language: Language,
+    game_state: Rc<GameStateSnapshot>,
+}
+
+/// Returns the CSS class for a character.
+pub fn character_class(character: Character) -> &'static str {
+    match character {
+        Character::BlueAgent => "blue-agent",
+        Character::RedAgent => "red-agent",
+        Character::Bystander => "bystander",
+        Character::Assassin => "assassin",
+        Character::Unknown => "unspotted",
+    }
+}
+
+impl ClueHistory {
+    fn view_entry(&self, entry: &ClueLogEntry) -> Html {
+        let s = self.language.strings();
+        let found = entry
+            .guesses
+            .iter()
+            .filter(|x| x.character == entry.team.agent())
+            .count();
+        html! {
+            <li class=match entry.team {
+                Team::Red => "team-red",
+                Team::Blue => "team-blue",
+            }>
+                <span class="clue">{self.language.clue_text(&entry.word, entry.number, entry.unlimited)}</span>
+                {" "}
+                <span class="found">
+                    {if entry.number == 0 {
+                        fill(s.clue_found, &[&found])
+                    } else {
+                        fill(s.clue_found_of, &[&found, &entry.number])
+                    }}
+                </span>
+                <ul class="guesses">
+                {
+                    for entry.guesses.iter().map(|guess| html! {
+                        <li class=character_class(guess.character)>
+                            {self.game_state
+                                .tiles
+                                .get(guess.index)
+                                .map_or("", |x| x.codeword.as_str())}
+                        </li>
+                    })
+                }
+                </ul>
+            </li>
+        }
+    }
+}
+
+impl Component for ClueHistory {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        ClueHistory {
+            language: props.language,
+            game_state: props.game_state,
+        }
+    }
+
+    fn update(&mut self, _: Self::Message) -> ShouldRender {
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.game_state != props.game_state || self.language != props.language {
+            self.language = props.language;
+            self.game_state = props.game_state;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let s = self.language.strings();
+        html! {
+            <div class="clues box">
+                <h2>{s.clue_history}</h2>
+                {if self.game_state.clues.is_empty() {
+                    html! {
+                        <p class="explanation">{s.no_clues}</p>
+                    }
+                } else {
+                    html! {
+                        <ul>
+                        {
+                            for self.game_state.clues.iter().map(|entry| self.view_entry(entry))
+                        }
+                        </ul>
+                    }
+                }}
+            </div>
+        }
+    }
+}
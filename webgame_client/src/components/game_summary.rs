@@ -0,0 +1,215 @@
+use std::rc::Rc;
+
+use uuid::Uuid;
+use yew::{html, Callback, Component, ComponentLink, Html, Properties, ShouldRender};
+
+use crate::components::clue_history::character_class;
+use crate::i18n::{fill, Language};
+use crate::protocol::{Character, GameStateSnapshot, PlayerRole, Team};
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub language: Language,
+    pub game_state: Rc<GameStateSnapshot>,
+    pub rematch_enabled: bool,
+    pub on_rematch: Callback<()>,
+    pub on_leave: Callback<()>,
+}
+
+/// Shows how a finished game went.
+///
+/// The clue history stays next to it in the game page.
+pub struct GameSummary {
+    link: ComponentLink<GameSummary>,
+    language: Language,
+    game_state: Rc<GameStateSnapshot>,
+    rematch_enabled: bool,
+    on_rematch: Callback<()>,
+    on_leave: Callback<()>,
+}
+
+pub enum Msg {
+    Rematch,
+    Leave,
+}
+
+/// What a player did during the game.
+#[derive(Default)]
+struct Contribution {
+    clues_given: usize,
+    clue_hits: usize,
+    guesses: usize,
+    correct_guesses: usize,
+}
+
+impl GameSummary {
+    fn team_name(&self, team: Team) -> &'static str {
+        let s = self.language.strings();
+        match team {
+            Team::Red => s.red,
+            Team::Blue => s.blue,
+        }
+    }
+
+    /// Explains why the game ended.
+    fn reason(&self, winner: Team) -> String {
+        let s = self.language.strings();
+        let assassin_found = self
+            .game_state
+            .tiles
+            .iter()
+            .any(|x| x.spotted && x.character == Character::Assassin);
+        if assassin_found {
+            fill(s.reason_assassin, &[&self.team_name(winner.other())])
+        } else {
+            fill(s.reason_all_agents, &[&self.team_name(winner)])
+        }
+    }
+
+    fn contribution(&self, player_id: Uuid) -> Contribution {
+        let mut rv = Contribution::default();
+        for entry in self.game_state.clues.iter() {
+            let hits = entry
+                .guesses
+                .iter()
+                .filter(|x| x.character == entry.team.agent());
+            if entry.player_id == player_id {
+                rv.clues_given += 1;
+                rv.clue_hits += hits.clone().count();
+            }
+            rv.guesses += entry
+                .guesses
+                .iter()
+                .filter(|x| x.player_id == player_id)
+                .count();
+            rv.correct_guesses += hits.filter(|x| x.player_id == player_id).count();
+        }
+        rv
+    }
+
+    fn view_key(&self) -> Html {
+        html! {
+            <div class="key">
+            {
+                for self.game_state.tiles.iter().map(|tile| html! {
+                    <div class={format!(
+                        "key-tile {}{}",
+                        character_class(tile.character),
+                        if tile.spotted { " spotted" } else { "" },
+                    )}>
+                        {&tile.codeword}
+                    </div>
+                })
+            }
+            </div>
+        }
+    }
+
+    fn view_contributions(&self) -> Html {
+        let s = self.language.strings();
+        html! {
+            <table class="stats">
+                <tr>
+                    <th>{s.leaderboard_player}</th>
+                    <th>{s.stats_clues_given}</th>
+                    <th>{s.contribution_clue_hits}</th>
+                    <th>{s.contribution_guesses}</th>
+                    <th>{s.contribution_correct_guesses}</th>
+                </tr>
+                {
+                    for self.game_state
+                        .players
+                        .iter()
+                        .filter(|x| x.team.is_some() && x.role != PlayerRole::Spectator)
+                        .map(|state| {
+                            let contribution = self.contribution(state.player.id);
+                            html! {
+                                <tr class=match state.team {
+                                    Some(Team::Red) => "team-red",
+                                    _ => "team-blue",
+                                }>
+                                    <td>{&state.player.nickname}</td>
+                                    <td>{contribution.clues_given}</td>
+                                    <td>{contribution.clue_hits}</td>
+                                    <td>{contribution.guesses}</td>
+                                    <td>{contribution.correct_guesses}</td>
+                                </tr>
+                            }
+                        })
+                }
+            </table>
+        }
+    }
+}
+
+impl Component for GameSummary {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        GameSummary {
+            link,
+            language: props.language,
+            game_state: props.game_state,
+            rematch_enabled: props.rematch_enabled,
+            on_rematch: props.on_rematch,
+            on_leave: props.on_leave,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Rematch => self.on_rematch.emit(()),
+            Msg::Leave => self.on_leave.emit(()),
+        }
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.game_state != props.game_state
+            || self.language != props.language
+            || self.rematch_enabled != props.rematch_enabled
+        {
+            self.language = props.language;
+            self.game_state = props.game_state;
+            self.rematch_enabled = props.rematch_enabled;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let s = self.language.strings();
+        let winner = match self.game_state.winner {
+            Some(winner) => winner,
+            None => return html! {},
+        };
+        html! {
+            <div class="summary">
+                <h2>
+                    {match winner {
+                        Team::Red => s.game_over_red,
+                        Team::Blue => s.game_over_blue,
+                    }}
+                </h2>
+                <p class="explanation">{self.reason(winner)}</p>
+                {self.view_key()}
+                <div class="box">
+                    <h2>{s.contributions}</h2>
+                    {self.view_contributions()}
+                </div>
+                <div class="toolbar">
+                    {if self.rematch_enabled {
+                        html! {
+                            <button class="primary" onclick=self.link.callback(|_| Msg::Rematch)>{s.rematch}</button>
+                        }
+                    } else {
+                        html! {}
+                    }}
+                    <button onclick=self.link.callback(|_| Msg::Leave)>{s.back_to_menu}</button>
+                </div>
+            </div>
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod chat_box;
+pub mod clue_form;
+pub mod clue_history;
+pub mod game_summary;
 pub mod player_list;
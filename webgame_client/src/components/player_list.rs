@@ -2,14 +2,17 @@ use std::rc::Rc;
 
 use yew::{html, Component, ComponentLink, Html, Properties, ShouldRender};
 
+use crate::i18n::Language;
 use crate::protocol::{GameStateSnapshot, PlayerRole, Team, Turn};
 
 #[derive(Clone, Properties)]
 pub struct Props {
+    pub language: Language,
     pub game_state: Rc<GameStateSnapshot>,
 }
 
 pub struct PlayerList {
+    language: Language,
     game_state: Rc<GameStateSnapshot>,
 }
 
@@ -19,6 +22,7 @@ impl Component for PlayerList {
 
     fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
         PlayerList {
+            language: props.language,
             game_state: props.game_state,
         }
     }
@@ -28,7 +32,8 @@ impl Component for PlayerList {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if self.game_state != props.game_state {
+        if self.game_state != props.game_state || self.language != props.language {
+            self.language = props.language;
             self.game_state = props.game_state;
             true
         } else {
@@ -37,9 +42,10 @@ impl Component for PlayerList {
     }
 
     fn view(&self) -> Html {
+        let s = self.language.strings();
         html! {
             <div class="players box">
-                <h2>{"Players"}</h2>
+                <h2>{s.players}</h2>
                 <ul>
                 {
                     for self.game_state.players.iter().map(|state| html! {
@@ -51,18 +57,19 @@ impl Component for PlayerList {
                             }
                         }>
                             <span class="nickname">{&state.player.nickname}</span>
+                            {if state.player.is_bot { s.player_bot } else { "" }}
                             {format!(
                                 " {}",
                                 match state.role {
-                                    PlayerRole::Spymaster => "(Spymaster)",
+                                    PlayerRole::Spymaster => s.player_spymaster,
                                     PlayerRole::Operative => "",
-                                    PlayerRole::Spectator => "(Spectator)",
+                                    PlayerRole::Spectator => s.player_spectator,
                                 }
                             )}
                             {
                                 if self.game_state.turn == Turn::Pregame &&
                                     state.ready {
-                                    html! { " — ready" }
+                                    html! { <>{s.player_ready}</> }
                                 } else {
                                     html!{}
                                 }
@@ -0,0 +1,164 @@
+use super::Strings;
+
+pub static STRINGS: Strings = Strings {
+    language: "Sprache:",
+
+    start_title: "Lass uns zusammen Codenames spielen",
+    start_explanation: "Gib dir einen Namen, um zu spielen:",
+    nickname_placeholder: "Spitzname",
+    play: "Spielen",
+    start_error: "Das ging schief: {0}",
+    accounts_explanation: "Oder melde dich an, um deinen Spitznamen zu behalten:",
+    username_placeholder: "Benutzername",
+    password_placeholder: "Passwort",
+    email_placeholder: "E-Mail (optional)",
+    token_placeholder: "Anmelde-Code",
+    sign_in: "Anmelden",
+    register: "Registrieren",
+    send_token: "Code per E-Mail",
+    sign_in_with_token: "Code verwenden",
+    account_registered: "Dein Konto wurde angelegt. Du kannst dich jetzt anmelden.",
+    token_sent: "Falls das Konto eine E-Mail-Adresse hat, wurde ein Anmelde-Code verschickt.",
+
+    menu_title: "Los geht's",
+    menu_greeting: "Hallo {0}!",
+    menu_explanation: "Starte ein neues Spiel oder gib den Code eines Spiels ein, um beizutreten.",
+    new_game: "Neues Spiel",
+    new_public_game: "Neues öffentliches Spiel",
+    join_game: "Beitreten",
+    spectators_see: "Zuschauer sehen:",
+    visibility_full: "die ganze Karte",
+    visibility_revealed_only: "nur aufgedeckte Karten",
+    visibility_delayed: "die Karte nach dem Spiel",
+    public_games: "Öffentliche Spiele",
+    no_public_games: "Gerade gibt es keine öffentlichen Spiele.",
+    game_hosted_by_one: " von {0} — {1} Spieler",
+    game_hosted_by_other: " von {0} — {1} Spieler",
+    game_in_progress: " (läuft bereits, zum Zuschauen beitreten)",
+    refresh: "Aktualisieren",
+    menu_error: "Oje: {0}",
+    stats: "Statistik",
+
+    stats_title: "Statistik",
+    stats_own: "Deine Spiele",
+    stats_sign_in: "Melde dich an, um eine Statistik deiner Spiele zu führen.",
+    stats_games_played: "Gespielt",
+    stats_games_won: "Gewonnen",
+    stats_as_spymaster: "Als Geheimdienstchef",
+    stats_as_operative: "Als Agent",
+    stats_won_of_played: "{0} von {1} gewonnen",
+    stats_assassin_hits: "Attentäter aufgedeckt",
+    stats_clues_given: "Hinweise gegeben",
+    stats_average_guesses: "Versuche pro Hinweis",
+    stats_clue_success_rate: "Erfolgreiche Hinweise",
+    leaderboard: "Bestenliste",
+    leaderboard_empty: "Bisher hat niemand mit Konto ein Spiel beendet.",
+    leaderboard_player: "Spieler",
+    back: "Zurück",
+
+    game_title: "Spiel ({0})",
+    game_over_red: "Spiel vorbei: Team Rot gewinnt",
+    game_over_blue: "Spiel vorbei: Team Blau gewinnt",
+    turn: "Zug: {0}",
+    turn_with_clue: "Zug: {0} (Hinweis: {1})",
+    turn_pregame: "vor dem Spiel",
+    turn_intermission: "Pause",
+    turn_red_spymaster: "roter Geheimdienstchef",
+    turn_red_operatives: "rote Agenten",
+    turn_blue_spymaster: "blauer Geheimdienstchef",
+    turn_blue_operatives: "blaue Agenten",
+    turn_end: "Ende",
+    clue_unlimited: "∞",
+    chat_channel: "Chat-Kanal",
+    to_all: "an alle",
+    to_team: "ans Team",
+    to_spectators: "an Zuschauer",
+    chat_placeholder: "schreib etwas",
+    share_codename: "Hinweis geben",
+    clue_placeholder: "Hinweis",
+    clue_number: "Anzahl:",
+    clue_confirm: "„{0}“ deinem Team als Hinweis geben?",
+    cancel: "Abbrechen",
+    reason_assassin: "Team {0} hat den Attentäter aufgedeckt.",
+    reason_all_agents: "Alle Agenten von Team {0} wurden gefunden.",
+    contributions: "Beiträge",
+    contribution_clue_hits: "Agenten durch Hinweise",
+    contribution_guesses: "Versuche",
+    contribution_correct_guesses: "Treffer",
+    rematch: "Revanche",
+    back_to_menu: "Zurück zum Menü",
+    send_chat: "Senden",
+    end_turn: "Zug beenden",
+    team_label: "Team:",
+    red: "Rot",
+    blue: "Blau",
+    spectate: "Zuschauen",
+    role_label: "Rolle:",
+    spymaster: "Geheimdienstchef",
+    operative: "Agent",
+    ready: "Bereit!",
+    disconnect: "Verlassen",
+    add_bot: "Bot hinzufügen:",
+    difficulty_easy: "leicht",
+    difficulty_normal: "normal",
+    difficulty_hard: "schwer",
+    red_spymaster: "Roter Geheimdienstchef",
+    blue_spymaster: "Blauer Geheimdienstchef",
+    red_operative: "Roter Agent",
+    blue_operative: "Blauer Agent",
+    server_nickname: "Server",
+    anonymous: "anonym",
+    reconnect_after: "{0} Versuche es in {1} Sekunden erneut.",
+
+    players: "Spieler",
+    player_bot: " (Bot)",
+    player_spymaster: "(Geheimdienstchef)",
+    player_spectator: "(Zuschauer)",
+    player_ready: " — bereit",
+
+    clue_history: "Hinweise",
+    no_clues: "Bisher wurden keine Hinweise gegeben.",
+    clue_found: "{0} gefunden",
+    clue_found_of: "{0} von {1} gefunden",
+
+    chat: "Chat",
+    chat_connected: "*verbunden*",
+    chat_disconnected: "*getrennt*",
+    chat_team: "[Team]",
+    chat_spectators: "[Zuschauer]",
+
+    error_connection_lost: "Die Verbindung zum Server wurde unterbrochen.",
+    error_unsupported_version:
+        "Diese Version des Spiels wird nicht mehr unterstützt. Bitte lade die Seite neu.",
+    error_nickname_length: "Bitte wähle einen kürzeren Spitznamen.",
+    error_nickname_length_limit: "Spitznamen müssen zwischen 1 und {0} Zeichen lang sein.",
+    error_nickname_characters: "Spitznamen dürfen keine unsichtbaren Zeichen enthalten.",
+    error_nickname_not_allowed: "Dieser Spitzname ist nicht erlaubt.",
+    error_text_too_long: "Die Nachricht ist zu lang.",
+    error_text_too_long_limit: "Nachrichten dürfen höchstens {0} Zeichen lang sein.",
+    error_text_not_allowed: "Diese Nachricht ist nicht erlaubt.",
+    error_rate_limited: "Du sendest zu schnell Anfragen.",
+    error_rate_limited_retry:
+        "Du sendest zu schnell Anfragen. Versuche es in {0} Sekunden erneut.",
+    error_game_not_found: "Es gibt kein Spiel mit diesem Code.",
+    error_not_in_game: "Du bist in keinem Spiel.",
+    error_game_already_started: "Das Spiel hat schon begonnen.",
+    error_game_not_over: "Das Spiel ist noch nicht vorbei.",
+    error_not_in_team: "Du musst zuerst einem Team beitreten.",
+    error_channel_not_allowed: "Du kannst in diesen Kanal nicht schreiben.",
+    error_bots_cannot_spectate: "Bots können nicht zuschauen.",
+    error_not_your_turn: "Du bist nicht am Zug.",
+    error_codename_not_single_word: "Der Hinweis muss ein einzelnes Wort sein.",
+    error_codename_on_board: "Der Hinweis darf kein Wort auf dem Spielfeld sein.",
+    error_card_not_revealable: "Diese Karte kann nicht aufgedeckt werden.",
+    error_guess_required: "Du musst mindestens einmal raten.",
+    error_accounts_disabled: "Dieser Server unterstützt keine Konten.",
+    error_invalid_username:
+        "Benutzernamen bestehen aus 3 bis {0} Buchstaben, Ziffern, Bindestrichen, Unterstrichen oder Punkten.",
+    error_username_taken: "Dieser Benutzername ist schon vergeben.",
+    error_password_too_short: "Passwörter müssen mindestens {0} Zeichen lang sein.",
+    error_invalid_email: "Diese E-Mail-Adresse ist ungültig.",
+    error_password_or_email_required: "Bitte gib ein Passwort oder eine E-Mail-Adresse ein.",
+    error_invalid_credentials: "Benutzername, Passwort oder Code sind falsch.",
+    error_server_error: "Auf dem Server ist etwas schiefgegangen. Bitte versuche es später erneut.",
+};
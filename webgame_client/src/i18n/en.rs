@@ -0,0 +1,163 @@
+use super::Strings;
+
+pub static STRINGS: Strings = Strings {
+    language: "Language:",
+
+    start_title: "Let's Play Codenames Together",
+    start_explanation: "Give yourself a name to play:",
+    nickname_placeholder: "nickname",
+    play: "Play",
+    start_error: "not good: {0}",
+    accounts_explanation: "Or sign in to an account to keep your nickname:",
+    username_placeholder: "username",
+    password_placeholder: "password",
+    email_placeholder: "email (optional)",
+    token_placeholder: "sign-in token",
+    sign_in: "Sign In",
+    register: "Register",
+    send_token: "Email Me a Token",
+    sign_in_with_token: "Use Token",
+    account_registered: "Your account was created. You can sign in now.",
+    token_sent: "If the account has an email address a sign-in token was sent to it.",
+
+    menu_title: "Let's get started",
+    menu_greeting: "Hello {0}!",
+    menu_explanation: "Start a new game or enter the code of a game to join.",
+    new_game: "New Game",
+    new_public_game: "New Public Game",
+    join_game: "Join Game",
+    spectators_see: "Spectators see:",
+    visibility_full: "the full key",
+    visibility_revealed_only: "revealed tiles only",
+    visibility_delayed: "the key after the game",
+    public_games: "Public Games",
+    no_public_games: "There are no public games right now.",
+    game_hosted_by_one: " hosted by {0} — {1} player",
+    game_hosted_by_other: " hosted by {0} — {1} players",
+    game_in_progress: " (in progress, join to watch)",
+    refresh: "Refresh",
+    menu_error: "uh oh: {0}",
+    stats: "Statistics",
+
+    stats_title: "Statistics",
+    stats_own: "Your Games",
+    stats_sign_in: "Sign in to an account to keep statistics of your own games.",
+    stats_games_played: "Games played",
+    stats_games_won: "Games won",
+    stats_as_spymaster: "As spymaster",
+    stats_as_operative: "As operative",
+    stats_won_of_played: "{0} won of {1}",
+    stats_assassin_hits: "Assassins revealed",
+    stats_clues_given: "Clues given",
+    stats_average_guesses: "Guesses per clue",
+    stats_clue_success_rate: "Successful clues",
+    leaderboard: "Leaderboard",
+    leaderboard_empty: "Nobody with an account finished a game yet.",
+    leaderboard_player: "Player",
+    back: "Back",
+
+    game_title: "Game ({0})",
+    game_over_red: "Game over: red team wins",
+    game_over_blue: "Game over: blue team wins",
+    turn: "Turn: {0}",
+    turn_with_clue: "Turn: {0} (codename: {1})",
+    turn_pregame: "pre-game",
+    turn_intermission: "intermission",
+    turn_red_spymaster: "red spymaster",
+    turn_red_operatives: "red operatives",
+    turn_blue_spymaster: "blue spymaster",
+    turn_blue_operatives: "blue operatives",
+    turn_end: "end",
+    clue_unlimited: "∞",
+    chat_channel: "chat channel",
+    to_all: "to all",
+    to_team: "to team",
+    to_spectators: "to spectators",
+    chat_placeholder: "send some text",
+    share_codename: "Share Codename",
+    clue_placeholder: "codename",
+    clue_number: "Number:",
+    clue_confirm: "Share “{0}” with your team?",
+    cancel: "Cancel",
+    reason_assassin: "Team {0} revealed the assassin.",
+    reason_all_agents: "All agents of team {0} were found.",
+    contributions: "Contributions",
+    contribution_clue_hits: "Agents found for clues",
+    contribution_guesses: "Guesses",
+    contribution_correct_guesses: "Correct guesses",
+    rematch: "Rematch",
+    back_to_menu: "Back to Menu",
+    send_chat: "Chat",
+    end_turn: "End Turn",
+    team_label: "Team:",
+    red: "Red",
+    blue: "Blue",
+    spectate: "Spectate",
+    role_label: "Role:",
+    spymaster: "Spymaster",
+    operative: "Operative",
+    ready: "Ready!",
+    disconnect: "Disconnect",
+    add_bot: "Add Bot:",
+    difficulty_easy: "easy",
+    difficulty_normal: "normal",
+    difficulty_hard: "hard",
+    red_spymaster: "Red Spymaster",
+    blue_spymaster: "Blue Spymaster",
+    red_operative: "Red Operative",
+    blue_operative: "Blue Operative",
+    server_nickname: "server",
+    anonymous: "anonymous",
+    reconnect_after: "{0} Try again in {1} seconds.",
+
+    players: "Players",
+    player_bot: " (Bot)",
+    player_spymaster: "(Spymaster)",
+    player_spectator: "(Spectator)",
+    player_ready: " — ready",
+
+    clue_history: "Clues",
+    no_clues: "No clues were given yet.",
+    clue_found: "{0} found",
+    clue_found_of: "{0} of {1} found",
+
+    chat: "Chat",
+    chat_connected: "*connected*",
+    chat_disconnected: "*disconnected*",
+    chat_team: "[team]",
+    chat_spectators: "[spectators]",
+
+    error_connection_lost: "The connection to the server was lost.",
+    error_unsupported_version:
+        "This version of the game is no longer supported. Please reload the page.",
+    error_nickname_length: "Please pick a shorter nickname.",
+    error_nickname_length_limit: "Nicknames must be between 1 and {0} characters.",
+    error_nickname_characters: "Nicknames cannot contain invisible characters.",
+    error_nickname_not_allowed: "This nickname is not allowed.",
+    error_text_too_long: "The message is too long.",
+    error_text_too_long_limit: "Messages can be at most {0} characters long.",
+    error_text_not_allowed: "This message is not allowed.",
+    error_rate_limited: "You are sending requests too quickly.",
+    error_rate_limited_retry: "You are sending requests too quickly. Try again in {0} seconds.",
+    error_game_not_found: "There is no game with this code.",
+    error_not_in_game: "You are not in a game.",
+    error_game_already_started: "The game has already started.",
+    error_game_not_over: "The game is not over yet.",
+    error_not_in_team: "You have to join a team first.",
+    error_channel_not_allowed: "You cannot write to this channel.",
+    error_bots_cannot_spectate: "Bots cannot be spectators.",
+    error_not_your_turn: "It is not your turn.",
+    error_codename_not_single_word: "The codename must be a single word.",
+    error_codename_on_board: "The codename cannot be a word on the board.",
+    error_card_not_revealable: "This card cannot be revealed.",
+    error_guess_required: "You have to guess at least once.",
+    error_accounts_disabled: "This server does not support accounts.",
+    error_invalid_username:
+        "Usernames must be 3 to {0} letters, digits, dashes, underscores or dots.",
+    error_username_taken: "This username is already taken.",
+    error_password_too_short: "Passwords must be at least {0} characters long.",
+    error_invalid_email: "This email address is not valid.",
+    error_password_or_email_required: "Please enter a password or an email address.",
+    error_invalid_credentials: "The username, password or token is wrong.",
+    error_server_error: "Something went wrong on the server. Please try again later.",
+};
@@ -0,0 +1,350 @@
+//! Translations of the user interface.
+//!
+//! Every language has a catalog of type `Strings`.  Texts with
+//! placeholders use `{0}`, `{1}` and so on which are filled in by `fill`
+//! so that translations can reorder them.
+use std::fmt;
+
+use crate::protocol::{ErrorCode, ProtocolError, Turn};
+
+mod de;
+mod en;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Default for Language {
+    fn default() -> Language {
+        Language::English
+    }
+}
+
+impl Language {
+    /// All languages in the order shown in the language switcher.
+    pub const ALL: &'static [Language] = &[Language::English, Language::German];
+
+    /// Returns the language code.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    /// Returns the name of the language in that language.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    /// Looks up a language by a tag like `de` or `de-AT`.
+    pub fn from_tag(tag: &str) -> Option<Language> {
+        let code = tag.split(&['-', '_'][..]).next()?;
+        Language::ALL
+            .iter()
+            .copied()
+            .find(|lang| lang.code().eq_ignore_ascii_case(code))
+    }
+
+    /// Picks the language stored by the switcher or the browser's language.
+    pub fn detect() -> Language {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return Language::default(),
+        };
+        if let Ok(Some(storage)) = window.local_storage() {
+            if let Ok(Some(code)) = storage.get_item("webgame.language") {
+                if let Some(lang) = Language::from_tag(&code) {
+                    return lang;
+                }
+            }
+        }
+        window
+            .navigator()
+            .language()
+            .and_then(|tag| Language::from_tag(&tag))
+            .unwrap_or_default()
+    }
+
+    /// Remembers the language for the next visit.
+    pub fn store(self) {
+        if let Some(Ok(Some(storage))) = web_sys::window().map(|x| x.local_storage()) {
+            storage.set_item("webgame.language", self.code()).ok();
+        }
+    }
+
+    /// Returns the message catalog.
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::English => &en::STRINGS,
+            Language::German => &de::STRINGS,
+        }
+    }
+
+    /// Returns the name of a turn.
+    pub fn turn_name(self, turn: Turn) -> &'static str {
+        let s = self.strings();
+        match turn {
+            Turn::Pregame => s.turn_pregame,
+            Turn::Intermission => s.turn_intermission,
+            Turn::RedSpymasterThinking => s.turn_red_spymaster,
+            Turn::RedOperativesGuessing => s.turn_red_operatives,
+            Turn::BlueSpymasterThinking => s.turn_blue_spymaster,
+            Turn::BlueOperativesGuessing => s.turn_blue_operatives,
+            Turn::Endgame => s.turn_end,
+        }
+    }
+
+    /// Returns a clue as shown to players, like `river 2`.
+    pub fn clue_text(self, word: &str, number: usize, unlimited: bool) -> String {
+        if unlimited {
+            format!("{} {}", word, self.strings().clue_unlimited)
+        } else {
+            format!("{} {}", word, number)
+        }
+    }
+
+    /// Returns a message for the user describing a protocol error.
+    ///
+    /// Messages are picked by the error code so they do not depend on the
+    /// wording used by the server.  Unknown codes fall back to the
+    /// server's English message.
+    pub fn describe_error(self, err: &ProtocolError) -> String {
+        let s = self.strings();
+        let details = err.details();
+        let limit = details.and_then(|x| x.limit);
+        match err.code() {
+            ErrorCode::ConnectionLost => s.error_connection_lost.into(),
+            ErrorCode::UnsupportedVersion | ErrorCode::HelloRequired => {
+                s.error_unsupported_version.into()
+            }
+            ErrorCode::InvalidNicknameLength => match limit {
+                Some(limit) => fill(s.error_nickname_length_limit, &[&limit]),
+                None => s.error_nickname_length.into(),
+            },
+            ErrorCode::InvalidNicknameCharacters => s.error_nickname_characters.into(),
+            ErrorCode::NicknameNotAllowed => s.error_nickname_not_allowed.into(),
+            ErrorCode::TextTooLong => match limit {
+                Some(limit) => fill(s.error_text_too_long_limit, &[&limit]),
+                None => s.error_text_too_long.into(),
+            },
+            ErrorCode::TextNotAllowed => s.error_text_not_allowed.into(),
+            ErrorCode::RateLimited => match details.and_then(|x| x.retry_after_ms) {
+                Some(ms) => fill(s.error_rate_limited_retry, &[&((ms + 999) / 1000)]),
+                None => s.error_rate_limited.into(),
+            },
+            ErrorCode::GameNotFound => s.error_game_not_found.into(),
+            ErrorCode::NotInGame => s.error_not_in_game.into(),
+            ErrorCode::GameAlreadyStarted => s.error_game_already_started.into(),
+            ErrorCode::GameNotOver => s.error_game_not_over.into(),
+            ErrorCode::NotInTeam => s.error_not_in_team.into(),
+            ErrorCode::ChannelNotAllowed => s.error_channel_not_allowed.into(),
+            ErrorCode::BotsCannotSpectate => s.error_bots_cannot_spectate.into(),
+            ErrorCode::NotYourTurn => s.error_not_your_turn.into(),
+            ErrorCode::CodenameNotSingleWord => s.error_codename_not_single_word.into(),
+            ErrorCode::CodenameOnBoard => s.error_codename_on_board.into(),
+            ErrorCode::CardNotRevealable => s.error_card_not_revealable.into(),
+            ErrorCode::GuessRequired => s.error_guess_required.into(),
+            ErrorCode::AccountsDisabled => s.error_accounts_disabled.into(),
+            ErrorCode::InvalidUsername => fill(s.error_invalid_username, &[&limit.unwrap_or(32)]),
+            ErrorCode::UsernameTaken => s.error_username_taken.into(),
+            ErrorCode::PasswordTooShort => fill(s.error_password_too_short, &[&limit.unwrap_or(8)]),
+            ErrorCode::InvalidEmail => s.error_invalid_email.into(),
+            ErrorCode::PasswordOrEmailRequired => s.error_password_or_email_required.into(),
+            ErrorCode::InvalidCredentials => s.error_invalid_credentials.into(),
+            ErrorCode::ServerError => s.error_server_error.into(),
+            _ => err.message().to_string(),
+        }
+    }
+}
+
+/// Replaces the `{0}`, `{1}`, ... placeholders in a text.
+pub fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut rv = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rv.push_str(&rest[..start]);
+        let arg = rest[start..].find('}').and_then(|end| {
+            let idx: usize = rest[start + 1..start + end].parse().ok()?;
+            Some((args.get(idx)?, start + end + 1))
+        });
+        match arg {
+            Some((arg, next)) => {
+                rv.push_str(&arg.to_string());
+                rest = &rest[next..];
+            }
+            None => {
+                rv.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    rv.push_str(rest);
+    rv
+}
+
+/// The texts of the user interface in one language.
+pub struct Strings {
+    pub language: &'static str,
+
+    pub start_title: &'static str,
+    pub start_explanation: &'static str,
+    pub nickname_placeholder: &'static str,
+    pub play: &'static str,
+    pub start_error: &'static str,
+    pub accounts_explanation: &'static str,
+    pub username_placeholder: &'static str,
+    pub password_placeholder: &'static str,
+    pub email_placeholder: &'static str,
+    pub token_placeholder: &'static str,
+    pub sign_in: &'static str,
+    pub register: &'static str,
+    pub send_token: &'static str,
+    pub sign_in_with_token: &'static str,
+    pub account_registered: &'static str,
+    pub token_sent: &'static str,
+
+    pub menu_title: &'static str,
+    pub menu_greeting: &'static str,
+    pub menu_explanation: &'static str,
+    pub new_game: &'static str,
+    pub new_public_game: &'static str,
+    pub join_game: &'static str,
+    pub spectators_see: &'static str,
+    pub visibility_full: &'static str,
+    pub visibility_revealed_only: &'static str,
+    pub visibility_delayed: &'static str,
+    pub public_games: &'static str,
+    pub no_public_games: &'static str,
+    pub game_hosted_by_one: &'static str,
+    pub game_hosted_by_other: &'static str,
+    pub game_in_progress: &'static str,
+    pub refresh: &'static str,
+    pub menu_error: &'static str,
+    pub stats: &'static str,
+
+    pub stats_title: &'static str,
+    pub stats_own: &'static str,
+    pub stats_sign_in: &'static str,
+    pub stats_games_played: &'static str,
+    pub stats_games_won: &'static str,
+    pub stats_as_spymaster: &'static str,
+    pub stats_as_operative: &'static str,
+    pub stats_won_of_played: &'static str,
+    pub stats_assassin_hits: &'static str,
+    pub stats_clues_given: &'static str,
+    pub stats_average_guesses: &'static str,
+    pub stats_clue_success_rate: &'static str,
+    pub leaderboard: &'static str,
+    pub leaderboard_empty: &'static str,
+    pub leaderboard_player: &'static str,
+    pub back: &'static str,
+
+    pub game_title: &'static str,
+    pub game_over_red: &'static str,
+    pub game_over_blue: &'static str,
+    pub turn: &'static str,
+    pub turn_with_clue: &'static str,
+    pub turn_pregame: &'static str,
+    pub turn_intermission: &'static str,
+    pub turn_red_spymaster: &'static str,
+    pub turn_red_operatives: &'static str,
+    pub turn_blue_spymaster: &'static str,
+    pub turn_blue_operatives: &'static str,
+    pub turn_end: &'static str,
+    pub clue_unlimited: &'static str,
+    pub chat_channel: &'static str,
+    pub to_all: &'static str,
+    pub to_team: &'static str,
+    pub to_spectators: &'static str,
+    pub chat_placeholder: &'static str,
+    pub share_codename: &'static str,
+    pub clue_placeholder: &'static str,
+    pub clue_number: &'static str,
+    pub clue_confirm: &'static str,
+    pub cancel: &'static str,
+    pub reason_assassin: &'static str,
+    pub reason_all_agents: &'static str,
+    pub contributions: &'static str,
+    pub contribution_clue_hits: &'static str,
+    pub contribution_guesses: &'static str,
+    pub contribution_correct_guesses: &'static str,
+    pub rematch: &'static str,
+    pub back_to_menu: &'static str,
+    pub send_chat: &'static str,
+    pub end_turn: &'static str,
+    pub team_label: &'static str,
+    pub red: &'static str,
+    pub blue: &'static str,
+    pub spectate: &'static str,
+    pub role_label: &'static str,
+    pub spymaster: &'static str,
+    pub operative: &'static str,
+    pub ready: &'static str,
+    pub disconnect: &'static str,
+    pub add_bot: &'static str,
+    pub difficulty_easy: &'static str,
+    pub difficulty_normal: &'static str,
+    pub difficulty_hard: &'static str,
+    pub red_spymaster: &'static str,
+    pub blue_spymaster: &'static str,
+    pub red_operative: &'static str,
+    pub blue_operative: &'static str,
+    pub server_nickname: &'static str,
+    pub anonymous: &'static str,
+    pub reconnect_after: &'static str,
+
+    pub players: &'static str,
+    pub player_bot: &'static str,
+    pub player_spymaster: &'static str,
+    pub player_spectator: &'static str,
+    pub player_ready: &'static str,
+
+    pub clue_history: &'static str,
+    pub no_clues: &'static str,
+    pub clue_found: &'static str,
+    pub clue_found_of: &'static str,
+
+    pub chat: &'static str,
+    pub chat_connected: &'static str,
+    pub chat_disconnected: &'static str,
+    pub chat_team: &'static str,
+    pub chat_spectators: &'static str,
+
+    pub error_connection_lost: &'static str,
+    pub error_unsupported_version: &'static str,
+    pub error_nickname_length: &'static str,
+    pub error_nickname_length_limit: &'static str,
+    pub error_nickname_characters: &'static str,
+    pub error_nickname_not_allowed: &'static str,
+    pub error_text_too_long: &'static str,
+    pub error_text_too_long_limit: &'static str,
+    pub error_text_not_allowed: &'static str,
+    pub error_rate_limited: &'static str,
+    pub error_rate_limited_retry: &'static str,
+    pub error_game_not_found: &'static str,
+    pub error_not_in_game: &'static str,
+    pub error_game_already_started: &'static str,
+    pub error_game_not_over: &'static str,
+    pub error_not_in_team: &'static str,
+    pub error_channel_not_allowed: &'static str,
+    pub error_bots_cannot_spectate: &'static str,
+    pub error_not_your_turn: &'static str,
+    pub error_codename_not_single_word: &'static str,
+    pub error_codename_on_board: &'static str,
+    pub error_card_not_revealable: &'static str,
+    pub error_guess_required: &'static str,
+    pub error_accounts_disabled: &'static str,
+    pub error_invalid_username: &'static str,
+    pub error_username_taken: &'static str,
+    pub error_password_too_short: &'static str,
+    pub error_invalid_email: &'static str,
+    pub error_password_or_email_required: &'static str,
+    pub error_invalid_credentials: &'static str,
+    pub error_server_error: &'static str,
+}
@@ -0,0 +1,81 @@
+use wasm_bindgen::JsValue;
+
+use crate::utils::format_join_code;
+
+/// A page of the client that can be addressed by URL.
+///
+/// Join codes are kept in the form the server uses (no dash).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    Start,
+    Games,
+    Join(String),
+    Game(String),
+}
+
+/// Turns a join code from a URL into the form the server uses.
+fn parse_join_code(code: &str) -> String {
+    format_join_code(code).replace("-", "")
+}
+
+impl Route {
+    /// Parses a URL path.  Unknown paths go to the start page.
+    pub fn from_path(path: &str) -> Route {
+        let mut segments = path.split('/').filter(|x| !x.is_empty());
+        match (segments.next(), segments.next(), segments.next()) {
+            (Some("games"), None, _) => Route::Games,
+            (Some("join"), Some(code), None) => Route::Join(parse_join_code(code)),
+            (Some("game"), Some(code), None) => Route::Game(parse_join_code(code)),
+            _ => Route::Start,
+        }
+    }
+
+    /// Returns the route for the current browser location.
+    pub fn current() -> Route {
+        web_sys::window()
+            .and_then(|window| window.location().pathname().ok())
+            .map_or(Route::Start, |path| Route::from_path(&path))
+    }
+
+    pub fn to_path(&self) -> String {
+        match *self {
+            Route::Start => "/".into(),
+            Route::Games => "/games".into(),
+            Route::Join(ref code) => format!("/join/{}", format_join_code(code)),
+            Route::Game(ref code) => format!("/game/{}", format_join_code(code)),
+        }
+    }
+
+    /// Returns the join code of a game route.
+    pub fn join_code(&self) -> Option<&str> {
+        match *self {
+            Route::Join(ref code) | Route::Game(ref code) => Some(code),
+            Route::Start | Route::Games => None,
+        }
+    }
+
+    /// Adds a history entry for this route unless it is the current one.
+    pub fn push(&self) {
+        self.update_history(false);
+    }
+
+    /// Replaces the current history entry with this route.
+    pub fn replace(&self) {
+        self.update_history(true);
+    }
+
+    fn update_history(&self, replace: bool) -> Option<()> {
+        let window = web_sys::window()?;
+        let path = self.to_path();
+        if window.location().pathname().ok()? == path {
+            return Some(());
+        }
+        let history = window.history().ok()?;
+        if replace {
+            history.replace_state_with_url(&JsValue::NULL, "", Some(&path))
+        } else {
+            history.push_state_with_url(&JsValue::NULL, "", Some(&path))
+        }
+        .ok()
+    }
+}
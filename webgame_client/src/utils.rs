@@ -1,3 +1,5 @@
+use uuid::Uuid;
+
 pub fn format_join_code(code: &str) -> String {
     let code = code.replace("-", "").to_ascii_uppercase();
     if code.len() > 3 {
@@ -6,3 +8,16 @@ pub fn format_join_code(code: &str) -> String {
         code
     }
 }
+
+/// Returns a token that identifies this browser across tabs.
+///
+/// The token is created on first use and kept in local storage.
+pub fn get_session_token() -> Option<String> {
+    let storage = web_sys::window()?.local_storage().ok()??;
+    if let Ok(Some(token)) = storage.get_item("webgame.session_token") {
+        return Some(token);
+    }
+    let token = Uuid::new_v4().to_string();
+    storage.set_item("webgame.session_token", &token).ok()?;
+    Some(token)
+}
@@ -11,11 +11,16 @@ use yew::{
 
 use crate::api::Api;
 use crate::components::chat_box::{ChatBox, ChatLine, ChatLineData};
+use crate::components::clue_form::ClueForm;
+use crate::components::clue_history::ClueHistory;
+use crate::components::game_summary::GameSummary;
 use crate::components::player_list::PlayerList;
+use crate::i18n::{fill, Language};
 use crate::protocol::{
-    Character, Command, GameInfo, GamePlayerState, GameStateSnapshot, Message, PlayerAction,
-    PlayerInfo, PlayerRole, RevealCardCommand, SendTextCommand, SetPlayerRoleCommand,
-    SetPlayerTeamCommand, Team, Tile, Turn,
+    AddBotCommand, BotDifficulty, Character, ChatChannel, Command, GameInfo, GamePlayerState,
+    GameStateSnapshot, Message, PlayerAction, PlayerInfo, PlayerRole, RevealCardCommand,
+    SendTextCommand, SetPlayerRoleCommand, SetPlayerTeamCommand, ShareCodenameCommand, Team, Tile,
+    Turn,
 };
 use crate::utils::format_join_code;
 
@@ -26,19 +31,25 @@ pub enum GamePageCommand {
 
 #[derive(Clone, Properties)]
 pub struct Props {
+    pub language: Language,
     pub player_info: PlayerInfo,
     pub game_info: GameInfo,
+    pub rematch_enabled: bool,
     pub on_game_command: Callback<GamePageCommand>,
 }
 
 pub struct GamePage {
     link: ComponentLink<GamePage>,
     api: Box<dyn Bridge<Api>>,
+    language: Language,
     game_info: GameInfo,
     player_info: PlayerInfo,
     game_state: Rc<GameStateSnapshot>,
     chat_line: String,
+    chat_channel: ChatChannel,
+    bot_difficulty: BotDifficulty,
     chat_log: Vector<Rc<ChatLine>>,
+    rematch_enabled: bool,
     on_game_command: Callback<GamePageCommand>,
 }
 
@@ -48,10 +59,16 @@ pub enum Msg {
     Disconnect,
     MarkReady,
     SetChatLine(String),
+    CycleChatChannel,
+    CycleBotDifficulty,
+    AddBot(Team, PlayerRole),
     ServerMessage(Message),
     JoinTeam(Option<Team>),
     SetRole(PlayerRole),
     Reveal(usize),
+    ShareCodename(ShareCodenameCommand),
+    EndTurn,
+    Rematch,
 }
 
 impl GamePage {
@@ -62,10 +79,13 @@ impl GamePage {
             .iter()
             .find(|x| x.player.id == player_id)
             .map(|x| x.player.nickname.as_str())
-            .unwrap_or("anonymous")
+            .unwrap_or(self.language.strings().anonymous)
             .to_string();
-        self.chat_log
-            .push_back(Rc::new(ChatLine { nickname, data }));
+        self.add_chat_line(ChatLine { nickname, data });
+    }
+
+    pub fn add_chat_line(&mut self, line: ChatLine) {
+        self.chat_log.push_back(Rc::new(line));
         while self.chat_log.len() > 100 {
             self.chat_log.pop_front();
         }
@@ -78,6 +98,21 @@ impl GamePage {
             .find(|state| state.player.id == self.player_info.id)
             .unwrap()
     }
+
+    /// Returns the chat channels the current player can post to.
+    pub fn available_chat_channels(&self) -> Vec<ChatChannel> {
+        let state = self.my_state();
+        let mut rv = vec![ChatChannel::All];
+        let in_play =
+            self.game_state.turn != Turn::Pregame && self.game_state.turn != Turn::Endgame;
+        if state.team.is_some() && !(state.role == PlayerRole::Spymaster && in_play) {
+            rv.push(ChatChannel::Team);
+        }
+        if state.role == PlayerRole::Spectator {
+            rv.push(ChatChannel::Spectators);
+        }
+        rv
+    }
 }
 
 fn get_tile_class(tile: &Tile, can_guess: bool) -> String {
@@ -105,14 +140,18 @@ impl Component for GamePage {
         GamePage {
             link,
             api,
+            language: props.language,
             game_info: props.game_info,
             chat_line: "".into(),
+            chat_channel: ChatChannel::All,
+            bot_difficulty: BotDifficulty::Normal,
             chat_log: Vector::unit(Rc::new(ChatLine {
                 nickname: props.player_info.nickname.clone(),
                 data: ChatLineData::Connected,
             })),
             game_state: Rc::new(GameStateSnapshot::default()),
             player_info: props.player_info,
+            rematch_enabled: props.rematch_enabled,
             on_game_command: props.on_game_command,
         }
     }
@@ -121,7 +160,7 @@ impl Component for GamePage {
         match msg {
             Msg::ServerMessage(message) => match message {
                 Message::Chat(msg) => {
-                    self.add_chat_message(msg.player_id, ChatLineData::Text(msg.text));
+                    self.add_chat_message(msg.player_id, ChatLineData::Text(msg.channel, msg.text));
                 }
                 Message::PlayerConnected(state) => {
                     let player_id = state.player.id;
@@ -134,50 +173,124 @@ impl Component for GamePage {
                     let game_state = Rc::make_mut(&mut self.game_state);
                     game_state.players.retain(|x| x.player.id != msg.player_id);
                 }
+                Message::ServerNotice(msg) => {
+                    self.add_chat_line(ChatLine {
+                        nickname: self.language.strings().server_nickname.into(),
+                        data: ChatLineData::Notice(msg.text),
+                    });
+                }
+                Message::ServerShutdown(msg) => {
+                    let s = self.language.strings();
+                    let text = match msg.reconnect_after {
+                        Some(secs) => fill(s.reconnect_after, &[&msg.reason, &secs]),
+                        None => msg.reason,
+                    };
+                    self.add_chat_line(ChatLine {
+                        nickname: s.server_nickname.into(),
+                        data: ChatLineData::Notice(text),
+                    });
+                }
                 Message::GameStateSnapshot(snapshot) => {
                     self.game_state = Rc::new(snapshot);
+                    // our role might have changed and with it the channels
+                    // we are allowed to post to.
+                    if !self.available_chat_channels().contains(&self.chat_channel) {
+                        self.chat_channel = ChatChannel::All;
+                    }
                 }
                 _ => {}
             },
             Msg::SendChat => {
                 let text = mem::replace(&mut self.chat_line, "".into());
-                self.api.send(Command::SendText(SendTextCommand { text }));
+                self.api.send(
+                    Command::SendText(SendTextCommand {
+                        text,
+                        channel: self.chat_channel,
+                    })
+                    .into(),
+                );
             }
             Msg::SetChatLine(text) => {
                 self.chat_line = text;
             }
+            Msg::CycleChatChannel => {
+                let channels = self.available_chat_channels();
+                let idx = channels
+                    .iter()
+                    .position(|&x| x == self.chat_channel)
+                    .map_or(0, |idx| (idx + 1) % channels.len());
+                self.chat_channel = channels[idx];
+            }
             Msg::JoinTeam(team) => {
                 self.api
-                    .send(Command::SetPlayerTeam(SetPlayerTeamCommand { team }));
+                    .send(Command::SetPlayerTeam(SetPlayerTeamCommand { team }).into());
             }
             Msg::SetRole(role) => {
                 self.api
-                    .send(Command::SetPlayerRole(SetPlayerRoleCommand { role }));
+                    .send(Command::SetPlayerRole(SetPlayerRoleCommand { role }).into());
+            }
+            Msg::CycleBotDifficulty => {
+                self.bot_difficulty = match self.bot_difficulty {
+                    BotDifficulty::Easy => BotDifficulty::Normal,
+                    BotDifficulty::Normal => BotDifficulty::Hard,
+                    BotDifficulty::Hard => BotDifficulty::Easy,
+                };
+            }
+            Msg::AddBot(team, role) => {
+                self.api.send(
+                    Command::AddBot(AddBotCommand {
+                        team,
+                        role,
+                        difficulty: self.bot_difficulty,
+                    })
+                    .into(),
+                );
             }
             Msg::MarkReady => {
-                self.api.send(Command::MarkReady);
+                self.api.send(Command::MarkReady.into());
             }
             Msg::Disconnect => {
-                self.api.send(Command::LeaveGame);
+                self.api.send(Command::LeaveGame.into());
             }
             Msg::Reveal(index) => {
                 if self.my_state().get_turn_player_action(self.game_state.turn)
                     == Some(PlayerAction::Guess)
                 {
                     self.api
-                        .send(Command::RevealCard(RevealCardCommand { index }));
+                        .send(Command::RevealCard(RevealCardCommand { index }).into());
                 }
             }
+            Msg::ShareCodename(cmd) => {
+                self.api.send(Command::ShareCodename(cmd).into());
+            }
+            Msg::EndTurn => {
+                self.api.send(Command::EndTurn.into());
+            }
+            Msg::Rematch => {
+                self.api.send(Command::Rematch.into());
+            }
             Msg::Ignore => {}
         }
         true
     }
 
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.language != props.language || self.rematch_enabled != props.rematch_enabled {
+            self.language = props.language;
+            self.rematch_enabled = props.rematch_enabled;
+            true
+        } else {
+            false
+        }
+    }
+
     fn view(&self) -> Html {
         if self.game_state.players.is_empty() {
             return html! {};
         }
 
+        let s = self.language.strings();
+
         let state = self.my_state();
 
         let team = state.team;
@@ -204,27 +317,76 @@ impl Component for GamePage {
 
         let player_action = state.get_turn_player_action(self.game_state.turn);
 
+        let turn_name = self.language.turn_name(self.game_state.turn);
+        let turn_info = match (self.game_state.winner, &self.game_state.clue) {
+            (Some(Team::Red), _) => s.game_over_red.to_string(),
+            (Some(Team::Blue), _) => s.game_over_blue.to_string(),
+            (None, Some(clue)) => fill(
+                s.turn_with_clue,
+                &[
+                    &turn_name,
+                    &self
+                        .language
+                        .clue_text(&clue.word, clue.number, clue.unlimited),
+                ],
+            ),
+            (None, None) => fill(s.turn, &[&turn_name]),
+        };
+
         html! {
             <div>
-                <p class="turn-info">{format!("Turn: {}", self.game_state.turn)}</p>
-                <h1>{format!("Game ({})", format_join_code(&self.game_info.join_code))}</h1>
-                <div class="box tiles">
-                {
-                    for self.game_state.tiles.iter().enumerate().map(|(idx, tile)| html! {
-                        <div
-                            class={get_tile_class(tile, player_action == Some(PlayerAction::Guess))}
-                            onclick=self.link.callback(move |_| Msg::Reveal(idx))>
-                            <span>{&tile.codeword}</span>
+                <p class="turn-info">{turn_info}</p>
+                <h1>{fill(s.game_title, &[&format_join_code(&self.game_info.join_code)])}</h1>
+                {if self.game_state.turn == Turn::Endgame {
+                    html! {
+                        <GameSummary
+                            language=self.language,
+                            game_state=self.game_state.clone(),
+                            rematch_enabled=self.rematch_enabled,
+                            on_rematch=self.link.callback(|_| Msg::Rematch),
+                            on_leave=self.link.callback(|_| Msg::Disconnect) />
+                    }
+                } else {
+                    html! {
+                        <div class="box tiles">
+                        {
+                            for self.game_state.tiles.iter().enumerate().map(|(idx, tile)| html! {
+                                <div
+                                    class={get_tile_class(tile, player_action == Some(PlayerAction::Guess))}
+                                    onclick=self.link.callback(move |_| Msg::Reveal(idx))>
+                                    <span>{&tile.codeword}</span>
+                                </div>
+                            })
+                        }
                         </div>
-                    })
-                }
-                </div>
-                <PlayerList game_state=self.game_state.clone()/>
-                <ChatBox log=self.chat_log.clone()/>
+                    }
+                }}
+                <PlayerList language=self.language, game_state=self.game_state.clone()/>
+                <ClueHistory language=self.language, game_state=self.game_state.clone()/>
+                <ChatBox language=self.language, log=self.chat_log.clone()/>
+                {if player_action == Some(PlayerAction::ShareCodename) {
+                    html! {
+                        <ClueForm
+                            language=self.language,
+                            game_state=self.game_state.clone(),
+                            on_share=self.link.callback(Msg::ShareCodename) />
+                    }
+                } else {
+                    html! {}
+                }}
                 <div class="toolbar">
-                    <span>{format!("{}: ", &self.player_info.nickname)}</span>
+                    <span>{format!("{}: ", &state.player.nickname)}</span>
+                    <button
+                        title=s.chat_channel
+                        onclick=self.link.callback(|_| Msg::CycleChatChannel)>
+                        {match self.chat_channel {
+                            ChatChannel::All => s.to_all,
+                            ChatChannel::Team => s.to_team,
+                            ChatChannel::Spectators => s.to_spectators,
+                        }}
+                    </button>
                     <input value=&self.chat_line
-                        placeholder="send some text"
+                        placeholder=s.chat_placeholder
                         size="30"
                         onkeypress=self.link.callback(|event: KeyboardEvent| {
                             if event.key() == "Enter" {
@@ -234,46 +396,70 @@ impl Component for GamePage {
                             }
                         })
                         oninput=self.link.callback(|e: InputData| Msg::SetChatLine(e.value)) />
-                    {if player_action == Some(PlayerAction::ShareCodename) {
+                    {if player_action == Some(PlayerAction::Guess) {
                         html! {
                             <>
-                                <button class="primary" onclick=self.link.callback(|_| Msg::SendChat)>{"Share Codename"}</button>
-                                <button onclick=self.link.callback(|_| Msg::SendChat)>{"Chat"}</button>
+                                <button class="primary" onclick=self.link.callback(|_| Msg::SendChat)>{s.send_chat}</button>
+                                <button onclick=self.link.callback(|_| Msg::EndTurn)>{s.end_turn}</button>
                             </>
                         }
                     } else {
                         html! {
-                            <button class="primary" onclick=self.link.callback(|_| Msg::SendChat)>{"Chat"}</button>
+                            <button class="primary" onclick=self.link.callback(|_| Msg::SendChat)>{s.send_chat}</button>
                         }
                     }}
                 </div>
                 {if self.game_state.turn == Turn::Pregame {
                     html! {
-                        <div class="toolbar">
-                            <span>{"Team:"}</span>
-                            {team_button(Some(Team::Red), "Red")}
-                            {team_button(Some(Team::Blue), "Blue")}
-                            {team_button(None, "Spectate")}
-                            {if team.is_some() {
-                                html! {
-                                    <>
-                                        <span>{"Role:"}</span>
-                                        {role_button(PlayerRole::Spymaster, "Spymaster")}
-                                        {role_button(PlayerRole::Operative, "Operative")}
-                                    </>
-                                }
-                            } else {
-                                html! {}
-                            }}
-                            {if state.team.is_some() {
-                                html! {
-                                    <button class="primary" onclick=self.link.callback(|_| Msg::MarkReady)>{"Ready!"}</button>
-                                }
-                            } else {
-                                html! {}
-                            }}
-                            <button class="cancel" onclick=self.link.callback(|_| Msg::Disconnect)>{"Disconnect"}</button>
-                        </div>
+                        <>
+                            <div class="toolbar">
+                                <span>{s.team_label}</span>
+                                {team_button(Some(Team::Red), s.red)}
+                                {team_button(Some(Team::Blue), s.blue)}
+                                {team_button(None, s.spectate)}
+                                {if team.is_some() {
+                                    html! {
+                                        <>
+                                            <span>{s.role_label}</span>
+                                            {role_button(PlayerRole::Spymaster, s.spymaster)}
+                                            {role_button(PlayerRole::Operative, s.operative)}
+                                        </>
+                                    }
+                                } else {
+                                    html! {}
+                                }}
+                                {if state.team.is_some() {
+                                    html! {
+                                        <button class="primary" onclick=self.link.callback(|_| Msg::MarkReady)>{s.ready}</button>
+                                    }
+                                } else {
+                                    html! {}
+                                }}
+                                <button class="cancel" onclick=self.link.callback(|_| Msg::Disconnect)>{s.disconnect}</button>
+                            </div>
+                            <div class="toolbar">
+                                <span>{s.add_bot}</span>
+                                <button onclick=self.link.callback(|_| Msg::CycleBotDifficulty)>
+                                    {match self.bot_difficulty {
+                                        BotDifficulty::Easy => s.difficulty_easy,
+                                        BotDifficulty::Normal => s.difficulty_normal,
+                                        BotDifficulty::Hard => s.difficulty_hard,
+                                    }}
+                                </button>
+                                <button onclick=self.link.callback(|_| Msg::AddBot(Team::Red, PlayerRole::Spymaster))>
+                                    {s.red_spymaster}
+                                </button>
+                                <button onclick=self.link.callback(|_| Msg::AddBot(Team::Blue, PlayerRole::Spymaster))>
+                                    {s.blue_spymaster}
+                                </button>
+                                <button onclick=self.link.callback(|_| Msg::AddBot(Team::Red, PlayerRole::Operative))>
+                                    {s.red_operative}
+                                </button>
+                                <button onclick=self.link.callback(|_| Msg::AddBot(Team::Blue, PlayerRole::Operative))>
+                                    {s.blue_operative}
+                                </button>
+                            </div>
+                        </>
                     }
                 } else {
                     html! {}
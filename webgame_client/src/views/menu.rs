@@ -4,71 +4,153 @@ use yew::{
     ShouldRender,
 };
 
-use crate::api::Api;
-use crate::protocol::{Command, GameInfo, JoinGameCommand, Message, PlayerInfo};
+use crate::api::{Api, Request};
+use crate::i18n::{fill, Language};
+use crate::protocol::{
+    Command, GameInfo, GameListEntry, JoinGameCommand, Message, NewGameCommand, PlayerInfo,
+    ProtocolError, SpectatorVisibility,
+};
 use crate::utils::format_join_code;
 
 #[derive(Clone, Properties)]
 pub struct Props {
+    pub language: Language,
     pub player_info: PlayerInfo,
+    pub stats_enabled: bool,
+    pub join_code: Option<String>,
     pub on_game_joined: Callback<GameInfo>,
+    pub on_show_stats: Callback<()>,
 }
 
 pub struct MenuPage {
     link: ComponentLink<MenuPage>,
     api: Box<dyn Bridge<Api>>,
     join_code: String,
+    link_join_code: Option<String>,
+    games: Vec<GameListEntry>,
+    spectator_visibility: SpectatorVisibility,
     player_info: PlayerInfo,
+    stats_enabled: bool,
     on_game_joined: Callback<GameInfo>,
-    error: Option<String>,
+    on_show_stats: Callback<()>,
+    language: Language,
+    error: Option<ProtocolError>,
 }
 
 pub enum Msg {
     Ignore,
-    NewGame,
+    NewGame(bool),
     JoinGame,
+    JoinListedGame(String),
+    RefreshGames,
+    ShowStats,
+    CycleSpectatorVisibility,
     ServerMessage(Message),
+    CommandFailed(ProtocolError),
     SetJoinCode(String),
 }
 
+impl MenuPage {
+    /// Sends a command and shows its error if it fails.
+    fn send_checked(&mut self, command: Command) {
+        self.error = None;
+        self.api
+            .send(
+                Request::new(command).on_response(self.link.callback(|result| match result {
+                    Ok(()) => Msg::Ignore,
+                    Err(err) => Msg::CommandFailed(err),
+                })),
+            );
+    }
+
+    /// Joins the game from a link once it changes.
+    fn follow_join_link(&mut self, join_code: Option<String>) -> bool {
+        if join_code == self.link_join_code {
+            return false;
+        }
+        self.link_join_code = join_code.clone();
+        if let Some(join_code) = join_code {
+            self.join_code = format_join_code(&join_code);
+            self.send_checked(Command::JoinGame(JoinGameCommand { join_code }));
+        }
+        true
+    }
+}
+
 impl Component for MenuPage {
     type Message = Msg;
     type Properties = Props;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         let on_server_message = link.callback(Msg::ServerMessage);
-        let api = Api::bridge(on_server_message);
-        MenuPage {
+        let mut api = Api::bridge(on_server_message);
+        api.send(Command::ListGames.into());
+        let mut page = MenuPage {
             link,
             api,
             join_code: "".into(),
+            link_join_code: None,
+            games: vec![],
+            spectator_visibility: SpectatorVisibility::Full,
             player_info: props.player_info,
+            stats_enabled: props.stats_enabled,
             on_game_joined: props.on_game_joined,
+            on_show_stats: props.on_show_stats,
+            language: props.language,
             error: None,
-        }
+        };
+        page.follow_join_link(props.join_code);
+        page
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            Msg::NewGame => {
+            Msg::NewGame(public) => {
                 log::info!("New Game");
-                self.api.send(Command::NewGame);
+                self.send_checked(Command::NewGame(NewGameCommand {
+                    public,
+                    spectator_visibility: self.spectator_visibility,
+                    ..Default::default()
+                }));
             }
             Msg::JoinGame => {
                 log::info!("Join Game");
-                self.api.send(Command::JoinGame(JoinGameCommand {
+                self.send_checked(Command::JoinGame(JoinGameCommand {
                     join_code: self.join_code.replace("-", ""),
                 }));
             }
+            Msg::JoinListedGame(join_code) => {
+                log::info!("Join Game");
+                self.send_checked(Command::JoinGame(JoinGameCommand { join_code }));
+            }
+            Msg::RefreshGames => {
+                self.api.send(Command::ListGames.into());
+            }
+            Msg::ShowStats => {
+                self.on_show_stats.emit(());
+            }
+            Msg::CycleSpectatorVisibility => {
+                self.spectator_visibility = match self.spectator_visibility {
+                    SpectatorVisibility::Full => SpectatorVisibility::RevealedOnly,
+                    SpectatorVisibility::RevealedOnly => SpectatorVisibility::Delayed,
+                    SpectatorVisibility::Delayed => SpectatorVisibility::Full,
+                };
+            }
             Msg::ServerMessage(message) => match message {
                 Message::GameJoined(data) => {
                     self.on_game_joined.emit(data);
                 }
+                Message::GameList(msg) => {
+                    self.games = msg.games;
+                }
                 Message::Error(err) => {
-                    self.error = Some(err.message().to_string());
+                    self.error = Some(err);
                 }
                 _ => {}
             },
+            Msg::CommandFailed(err) => {
+                self.error = Some(err);
+            }
             Msg::SetJoinCode(join_code) => {
                 self.join_code = format_join_code(&join_code);
             }
@@ -77,14 +159,27 @@ impl Component for MenuPage {
         true
     }
 
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let joining = self.follow_join_link(props.join_code);
+        if self.language != props.language || self.stats_enabled != props.stats_enabled {
+            self.language = props.language;
+            self.stats_enabled = props.stats_enabled;
+            true
+        } else {
+            joining
+        }
+    }
+
     fn view(&self) -> Html {
+        let s = self.language.strings();
         html! {
             <div>
-                <h1>{"Let's get started"}</h1>
-                <p class="intro">{format!("Hello {}!", &self.player_info.nickname)}</p>
-                <p class="explanation">{"Start a new game or enter the code of a game to join."}</p>
+                <h1>{s.menu_title}</h1>
+                <p class="intro">{fill(s.menu_greeting, &[&self.player_info.nickname])}</p>
+                <p class="explanation">{s.menu_explanation}</p>
                 <div class="toolbar">
-                    <button onclick=self.link.callback(|_| Msg::NewGame)>{"New Game"}</button>
+                    <button onclick=self.link.callback(|_| Msg::NewGame(false))>{s.new_game}</button>
+                    <button onclick=self.link.callback(|_| Msg::NewGame(true))>{s.new_public_game}</button>
                     <input value=&self.join_code
                         size="7"
                         placeholder="JOINCOD"
@@ -96,12 +191,69 @@ impl Component for MenuPage {
                             }
                         })
                         oninput=self.link.callback(|e: InputData| Msg::SetJoinCode(e.value)) />
-                    <button onclick=self.link.callback(|_| Msg::JoinGame)>{"Join Game"}</button>
+                    <button onclick=self.link.callback(|_| Msg::JoinGame)>{s.join_game}</button>
+                    {if self.stats_enabled {
+                        html! {
+                            <button onclick=self.link.callback(|_| Msg::ShowStats)>{s.stats}</button>
+                        }
+                    } else {
+                        html! {}
+                    }}
+                </div>
+                <div class="toolbar">
+                    <span>{s.spectators_see}</span>
+                    <button onclick=self.link.callback(|_| Msg::CycleSpectatorVisibility)>
+                        {match self.spectator_visibility {
+                            SpectatorVisibility::Full => s.visibility_full,
+                            SpectatorVisibility::RevealedOnly => s.visibility_revealed_only,
+                            SpectatorVisibility::Delayed => s.visibility_delayed,
+                        }}
+                    </button>
+                </div>
+                <div class="lobby box">
+                    <h2>{s.public_games}</h2>
+                    {if self.games.is_empty() {
+                        html! {
+                            <p class="explanation">{s.no_public_games}</p>
+                        }
+                    } else {
+                        html! {
+                            <ul>
+                            {
+                                for self.games.iter().map(|game| {
+                                    let join_code = game.join_code.clone();
+                                    html! {
+                                        <li>
+                                            <button
+                                                onclick=self.link.callback(move |_| Msg::JoinListedGame(join_code.clone()))>
+                                                {format_join_code(&game.join_code)}
+                                            </button>
+                                            {fill(
+                                                if game.player_count == 1 {
+                                                    s.game_hosted_by_one
+                                                } else {
+                                                    s.game_hosted_by_other
+                                                },
+                                                &[&game.host_nickname, &game.player_count],
+                                            )}
+                                            {if game.is_pregame { "" } else { s.game_in_progress }}
+                                        </li>
+                                    }
+                                })
+                            }
+                            </ul>
+                        }
+                    }}
+                    <div class="toolbar">
+                        <button onclick=self.link.callback(|_| Msg::RefreshGames)>{s.refresh}</button>
+                    </div>
                 </div>
                 {
                     if let Some(ref error) = self.error {
                         html! {
-                            <p class="error">{format!("uh oh: {}", error)}</p>
+                            <p class="error">
+                                {fill(s.menu_error, &[&self.language.describe_error(error)])}
+                            </p>
                         }
                     } else {
                         html!{}
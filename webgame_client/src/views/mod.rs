@@ -1,3 +1,4 @@
 pub mod game;
 pub mod menu;
 pub mod start;
+pub mod stats;
@@ -4,11 +4,17 @@ use yew::{
     ShouldRender,
 };
 
-use crate::api::Api;
-use crate::protocol::{AuthenticateCommand, Command, Message, PlayerInfo};
+use crate::api::{Api, Request};
+use crate::i18n::{fill, Language};
+use crate::protocol::{
+    AuthenticateCommand, Command, Credentials, Message, PlayerInfo, ProtocolError, RegisterCommand,
+    RequestMagicTokenCommand,
+};
+use crate::utils::get_session_token;
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct Props {
+    pub language: Language,
     pub on_authenticate: Callback<PlayerInfo>,
 }
 
@@ -16,17 +22,64 @@ pub struct StartPage {
     link: ComponentLink<StartPage>,
     api: Box<dyn Bridge<Api>>,
     nickname: String,
+    username: String,
+    password: String,
+    email: String,
+    token: String,
+    accounts_enabled: bool,
     on_authenticate: Callback<PlayerInfo>,
-    error: Option<String>,
+    language: Language,
+    notice: Option<Notice>,
+    error: Option<ProtocolError>,
+}
+
+/// Tells the player that an account command went through.
+#[derive(Clone, Copy)]
+pub enum Notice {
+    Registered,
+    TokenSent,
 }
 
 pub enum Msg {
     Authenticate,
+    SignIn,
+    SignInWithToken,
+    Register,
+    RequestToken,
     ServerMessage(Message),
+    AuthenticateFailed(ProtocolError),
+    Succeeded(Notice),
     SetNickname(String),
+    SetUsername(String),
+    SetPassword(String),
+    SetEmail(String),
+    SetToken(String),
     Ignore,
 }
 
+impl StartPage {
+    /// Sends a command and reports its outcome back to the page.
+    fn send_command(&mut self, command: Command, on_success: Option<Notice>) {
+        self.error = None;
+        self.notice = None;
+        self.api.send(
+            Request::new(command).on_response(self.link.callback(move |result| match result {
+                Ok(()) => on_success.map_or(Msg::Ignore, Msg::Succeeded),
+                Err(err) => Msg::AuthenticateFailed(err),
+            })),
+        );
+    }
+
+    fn authenticate(&mut self, credentials: Option<Credentials>) {
+        let command = Command::Authenticate(AuthenticateCommand {
+            nickname: self.nickname.clone(),
+            session_token: get_session_token(),
+            credentials,
+        });
+        self.send_command(command, None);
+    }
+}
+
 impl Component for StartPage {
     type Message = Msg;
     type Properties = Props;
@@ -38,7 +91,14 @@ impl Component for StartPage {
             link,
             api,
             nickname: "".into(),
+            username: "".into(),
+            password: "".into(),
+            email: "".into(),
+            token: "".into(),
+            accounts_enabled: false,
             on_authenticate: props.on_authenticate,
+            language: props.language,
+            notice: None,
             error: None,
         }
     }
@@ -46,37 +106,93 @@ impl Component for StartPage {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::Authenticate => {
-                self.api.send(Command::Authenticate(AuthenticateCommand {
-                    nickname: self.nickname.clone(),
+                self.authenticate(None);
+            }
+            Msg::SignIn => {
+                self.authenticate(Some(Credentials::Password {
+                    username: self.username.clone(),
+                    password: self.password.clone(),
                 }));
             }
+            Msg::SignInWithToken => {
+                self.authenticate(Some(Credentials::MagicToken {
+                    token: self.token.clone(),
+                }));
+            }
+            Msg::Register => {
+                let command = Command::Register(RegisterCommand {
+                    username: self.username.clone(),
+                    password: Some(self.password.clone()),
+                    email: Some(self.email.clone()),
+                });
+                self.send_command(command, Some(Notice::Registered));
+            }
+            Msg::RequestToken => {
+                let command = Command::RequestMagicToken(RequestMagicTokenCommand {
+                    username: self.username.clone(),
+                });
+                self.send_command(command, Some(Notice::TokenSent));
+            }
             Msg::ServerMessage(message) => match message {
+                Message::Hello(hello) => {
+                    self.accounts_enabled = hello.features.iter().any(|x| x == "accounts");
+                }
                 Message::Authenticated(data) => {
                     self.on_authenticate.emit(data);
                 }
+                // errors not caused by a command, like a protocol version
+                // mismatch.
                 Message::Error(err) => {
-                    self.error = Some(err.message().to_string());
+                    self.error = Some(err);
                 }
                 _ => {}
             },
+            Msg::AuthenticateFailed(err) => {
+                self.error = Some(err);
+            }
+            Msg::Succeeded(notice) => {
+                self.notice = Some(notice);
+            }
             Msg::SetNickname(nickname) => {
                 self.nickname = nickname;
             }
+            Msg::SetUsername(username) => {
+                self.username = username;
+            }
+            Msg::SetPassword(password) => {
+                self.password = password;
+            }
+            Msg::SetEmail(email) => {
+                self.email = email;
+            }
+            Msg::SetToken(token) => {
+                self.token = token;
+            }
             Msg::Ignore => {}
         }
         true
     }
 
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.language != props.language {
+            self.language = props.language;
+            true
+        } else {
+            false
+        }
+    }
+
     fn view(&self) -> Html {
+        let s = self.language.strings();
         html! {
             <div>
-                <h1>{"Let's Play Codenames Together"}</h1>
+                <h1>{s.start_title}</h1>
                 <p class="explanation">
-                    {"Give yourself a name to play:"}
+                    {s.start_explanation}
                 </p>
                 <div class="toolbar">
                     <input value=&self.nickname
-                        placeholder="nickname"
+                        placeholder=s.nickname_placeholder
                         onkeypress=self.link.callback(|event: KeyboardEvent| {
                             dbg!(event.key());
                             if event.key() == "Enter" {
@@ -88,12 +204,63 @@ impl Component for StartPage {
                         oninput=self.link.callback(|e: InputData| Msg::SetNickname(e.value)) />
                     <button
                         class="primary"
-                        onclick=self.link.callback(|_| Msg::Authenticate)>{"Play"}</button>
+                        onclick=self.link.callback(|_| Msg::Authenticate)>{s.play}</button>
                 </div>
+                {if self.accounts_enabled {
+                    html! {
+                        <>
+                            <p class="explanation">{s.accounts_explanation}</p>
+                            <div class="toolbar">
+                                <input value=&self.username
+                                    placeholder=s.username_placeholder
+                                    oninput=self.link.callback(|e: InputData| Msg::SetUsername(e.value)) />
+                                <input value=&self.password
+                                    type="password"
+                                    placeholder=s.password_placeholder
+                                    onkeypress=self.link.callback(|event: KeyboardEvent| {
+                                        if event.key() == "Enter" {
+                                            Msg::SignIn
+                                        } else {
+                                            Msg::Ignore
+                                        }
+                                    })
+                                    oninput=self.link.callback(|e: InputData| Msg::SetPassword(e.value)) />
+                                <button onclick=self.link.callback(|_| Msg::SignIn)>{s.sign_in}</button>
+                            </div>
+                            <div class="toolbar">
+                                <input value=&self.email
+                                    type="email"
+                                    placeholder=s.email_placeholder
+                                    oninput=self.link.callback(|e: InputData| Msg::SetEmail(e.value)) />
+                                <button onclick=self.link.callback(|_| Msg::Register)>{s.register}</button>
+                                <button onclick=self.link.callback(|_| Msg::RequestToken)>{s.send_token}</button>
+                            </div>
+                            <div class="toolbar">
+                                <input value=&self.token
+                                    placeholder=s.token_placeholder
+                                    oninput=self.link.callback(|e: InputData| Msg::SetToken(e.value)) />
+                                <button onclick=self.link.callback(|_| Msg::SignInWithToken)>{s.sign_in_with_token}</button>
+                            </div>
+                        </>
+                    }
+                } else {
+                    html! {}
+                }}
+                {match self.notice {
+                    Some(Notice::Registered) => html! {
+                        <p class="explanation">{s.account_registered}</p>
+                    },
+                    Some(Notice::TokenSent) => html! {
+                        <p class="explanation">{s.token_sent}</p>
+                    },
+                    None => html! {},
+                }}
                 {
                     if let Some(ref error) = self.error {
                         html! {
-                            <p class="error">{format!("not good: {}", error)}</p>
+                            <p class="error">
+                                {fill(s.start_error, &[&self.language.describe_error(error)])}
+                            </p>
                         }
                     } else {
                         html!{}
@@ -0,0 +1,215 @@
+use yew::agent::Bridged;
+use yew::{html, Bridge, Callback, Component, ComponentLink, Html, Properties, ShouldRender};
+
+use crate::api::{Api, Request};
+use crate::i18n::{fill, Language};
+use crate::protocol::{Command, LeaderboardEntry, Message, PlayerStats, ProtocolError};
+
+#[derive(Clone, Properties)]
+pub struct Props {
+    pub language: Language,
+    pub on_back: Callback<()>,
+}
+
+pub struct StatsPage {
+    link: ComponentLink<StatsPage>,
+    _api: Box<dyn Bridge<Api>>,
+    stats: Option<PlayerStats>,
+    leaderboard: Vec<LeaderboardEntry>,
+    loaded: bool,
+    on_back: Callback<()>,
+    language: Language,
+    error: Option<ProtocolError>,
+}
+
+pub enum Msg {
+    Ignore,
+    Back,
+    ServerMessage(Message),
+    CommandFailed(ProtocolError),
+}
+
+/// Formats a share as a rounded percentage.
+fn format_rate(rate: Option<f64>) -> String {
+    match rate {
+        Some(rate) => format!("{:.0}%", rate * 100.0),
+        None => "—".into(),
+    }
+}
+
+impl StatsPage {
+    fn view_own_stats(&self, stats: &PlayerStats) -> Html {
+        let s = self.language.strings();
+        let rows = [
+            (s.stats_games_played, stats.games_played().to_string()),
+            (s.stats_games_won, stats.games_won().to_string()),
+            (
+                s.stats_as_spymaster,
+                fill(
+                    s.stats_won_of_played,
+                    &[&stats.games_won_spymaster, &stats.games_played_spymaster],
+                ),
+            ),
+            (
+                s.stats_as_operative,
+                fill(
+                    s.stats_won_of_played,
+                    &[&stats.games_won_operative, &stats.games_played_operative],
+                ),
+            ),
+            (s.stats_assassin_hits, stats.assassin_hits.to_string()),
+            (s.stats_clues_given, stats.clues_given.to_string()),
+            (
+                s.stats_average_guesses,
+                match stats.average_guesses_per_clue() {
+                    Some(average) => format!("{:.1}", average),
+                    None => "—".into(),
+                },
+            ),
+            (
+                s.stats_clue_success_rate,
+                format_rate(stats.clue_success_rate()),
+            ),
+        ];
+        html! {
+            <table class="stats">
+                {for rows.iter().map(|(label, value)| html! {
+                    <tr>
+                        <th>{label}</th>
+                        <td>{value}</td>
+                    </tr>
+                })}
+            </table>
+        }
+    }
+
+    fn view_leaderboard(&self) -> Html {
+        let s = self.language.strings();
+        if self.leaderboard.is_empty() {
+            return html! {
+                <p class="explanation">{s.leaderboard_empty}</p>
+            };
+        }
+        html! {
+            <table class="stats">
+                <tr>
+                    <th>{"#"}</th>
+                    <th>{s.leaderboard_player}</th>
+                    <th>{s.stats_games_played}</th>
+                    <th>{s.stats_games_won}</th>
+                    <th>{s.stats_clue_success_rate}</th>
+                </tr>
+                {for self.leaderboard.iter().enumerate().map(|(idx, entry)| html! {
+                    <tr>
+                        <td>{idx + 1}</td>
+                        <td>{&entry.username}</td>
+                        <td>{entry.stats.games_played()}</td>
+                        <td>{entry.stats.games_won()}</td>
+                        <td>{format_rate(entry.stats.clue_success_rate())}</td>
+                    </tr>
+                })}
+            </table>
+        }
+    }
+}
+
+impl Component for StatsPage {
+    type Message = Msg;
+    type Properties = Props;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let on_server_message = link.callback(Msg::ServerMessage);
+        let mut api = Api::bridge(on_server_message);
+        api.send(Request::new(Command::GetStats).on_response(link.callback(
+            |result| match result {
+                Ok(()) => Msg::Ignore,
+                Err(err) => Msg::CommandFailed(err),
+            },
+        )));
+        StatsPage {
+            link,
+            _api: api,
+            stats: None,
+            leaderboard: vec![],
+            loaded: false,
+            on_back: props.on_back,
+            language: props.language,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Back => {
+                self.on_back.emit(());
+            }
+            Msg::ServerMessage(Message::Stats(msg)) => {
+                self.stats = msg.stats;
+                self.leaderboard = msg.leaderboard;
+                self.loaded = true;
+            }
+            Msg::ServerMessage(_) => return false,
+            Msg::CommandFailed(err) => {
+                self.error = Some(err);
+            }
+            Msg::Ignore => return false,
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.language != props.language {
+            self.language = props.language;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let s = self.language.strings();
+        html! {
+            <div>
+                <h1>{s.stats_title}</h1>
+                {if !self.loaded {
+                    html! {}
+                } else if let Some(ref stats) = self.stats {
+                    html! {
+                        <div class="box">
+                            <h2>{s.stats_own}</h2>
+                            {self.view_own_stats(stats)}
+                        </div>
+                    }
+                } else {
+                    html! {
+                        <p class="explanation">{s.stats_sign_in}</p>
+                    }
+                }}
+                {if self.loaded {
+                    html! {
+                        <div class="box">
+                            <h2>{s.leaderboard}</h2>
+                            {self.view_leaderboard()}
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }}
+                <div class="toolbar">
+                    <button onclick=self.link.callback(|_| Msg::Back)>{s.back}</button>
+                </div>
+                {
+                    if let Some(ref error) = self.error {
+                        html! {
+                            <p class="error">
+                                {fill(s.menu_error, &[&self.language.describe_error(error)])}
+                            </p>
+                        }
+                    } else {
+                        html!{}
+                    }
+                }
+            </div>
+        }
+    }
+}
@@ -10,17 +10,23 @@ module.exports = (env, argv) => {
       compress: argv.mode === 'production',
       host: '0.0.0.0',
       port: 8001,
+      historyApiFallback: true,
       proxy: {
         "/ws": {
           target: "http://127.0.0.1:8002",
           changeOrigin: true,
           ws: true,
+        },
+        "/api": {
+          target: "http://127.0.0.1:8002",
+          changeOrigin: true,
         }
       },
     },
     entry: './bootstrap.js',
     output: {
       path: distPath,
+      publicPath: "/",
       filename: "webgame.js",
       webassemblyModuleFilename: "webgame.wasm",
     },
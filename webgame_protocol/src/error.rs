@@ -0,0 +1,379 @@
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolErrorKind {
+    /// Client tried to authenticate twice
+    AlreadyAuthenticated,
+    /// Tried to do something while unauthenticated
+    NotAuthenticated,
+    /// Client sent in some garbage
+    InvalidCommand,
+    /// Cannot be done at this time
+    BadState,
+    /// Something wasn't found
+    NotFound,
+    /// Invalid input.
+    BadInput,
+    /// This should never happen.
+    InternalError,
+    /// The client speaks a protocol version the server does not support.
+    UnsupportedVersion,
+}
+
+/// Identifies the reason for an error.
+///
+/// Codes are stable across releases and meant for clients to show their
+/// own localized message.  On the wire they are snake_case strings and
+/// codes a client does not know about are read as `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// An error code this client does not know about.
+    Unknown,
+    /// The websocket frame was neither text nor binary.
+    InvalidFrame,
+    /// A binary frame was sent without negotiating MessagePack.
+    EncodingNotNegotiated,
+    /// The command could not be decoded.
+    MalformedCommand,
+    /// A command was sent before `Command::Hello`.
+    HelloRequired,
+    /// The protocol version is not supported.  The details list the
+    /// supported versions.
+    UnsupportedVersion,
+    /// `Command::Hello` was sent twice or after authenticating.
+    AlreadySaidHello,
+    /// The command requires authentication.
+    NotAuthenticated,
+    /// The player already authenticated.
+    AlreadyAuthenticated,
+    /// The player's connection is no longer known to the server.
+    PlayerNotFound,
+    /// The client lost the connection to the server.  This is never sent
+    /// by the server.
+    ConnectionLost,
+    /// The nickname is empty or too long.  The details carry the limit.
+    InvalidNicknameLength,
+    /// The nickname contains control or invisible characters.
+    InvalidNicknameCharacters,
+    /// The nickname was rejected by the blocklist.
+    NicknameNotAllowed,
+    /// A chat message or codename is too long.  The details carry the
+    /// limit.
+    TextTooLong,
+    /// A chat message or codename was rejected by the blocklist.
+    TextNotAllowed,
+    /// The player sends messages too quickly.  The details carry a retry
+    /// hint.
+    RateLimited,
+    /// There is no game with the join code.
+    GameNotFound,
+    /// The command requires the player to be in a game.
+    NotInGame,
+    /// The command is only possible before the game started.
+    GameAlreadyStarted,
+    /// The command is only possible once the game is over.
+    GameNotOver,
+    /// The command requires the player to be on a team.
+    NotInTeam,
+    /// The player may not post to the chat channel.
+    ChannelNotAllowed,
+    /// Bots can only be added as spymaster or operative.
+    BotsCannotSpectate,
+    /// It is not the player's turn.
+    NotYourTurn,
+    /// The codename consists of more than one word.
+    CodenameNotSingleWord,
+    /// The codename is one of the words on the board.
+    CodenameOnBoard,
+    /// The card does not exist or was already revealed.
+    CardNotRevealable,
+    /// Operatives have to guess at least once before ending their turn.
+    GuessRequired,
+    /// The server does not support accounts.
+    AccountsDisabled,
+    /// The username is too short, too long or contains characters other
+    /// than ASCII letters, digits, `-`, `_` and `.`.  The details carry
+    /// the maximum length.
+    InvalidUsername,
+    /// There already is an account with this username.
+    UsernameTaken,
+    /// The password is too short.  The details carry the minimum length.
+    PasswordTooShort,
+    /// The email address is not valid.
+    InvalidEmail,
+    /// An account needs a password or an email address to sign in.
+    PasswordOrEmailRequired,
+    /// The username, password or sign-in token is wrong.
+    InvalidCredentials,
+    /// The server failed to handle the command, for instance because its
+    /// database is unavailable.
+    ServerError,
+}
+
+impl Default for ErrorCode {
+    fn default() -> ErrorCode {
+        ErrorCode::Unknown
+    }
+}
+
+impl ErrorCode {
+    /// Returns the name of the code as sent on the wire.
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorCode::Unknown => "unknown",
+            ErrorCode::InvalidFrame => "invalid_frame",
+            ErrorCode::EncodingNotNegotiated => "encoding_not_negotiated",
+            ErrorCode::MalformedCommand => "malformed_command",
+            ErrorCode::HelloRequired => "hello_required",
+            ErrorCode::UnsupportedVersion => "unsupported_version",
+            ErrorCode::AlreadySaidHello => "already_said_hello",
+            ErrorCode::NotAuthenticated => "not_authenticated",
+            ErrorCode::AlreadyAuthenticated => "already_authenticated",
+            ErrorCode::PlayerNotFound => "player_not_found",
+            ErrorCode::ConnectionLost => "connection_lost",
+            ErrorCode::InvalidNicknameLength => "invalid_nickname_length",
+            ErrorCode::InvalidNicknameCharacters => "invalid_nickname_characters",
+            ErrorCode::NicknameNotAllowed => "nickname_not_allowed",
+            ErrorCode::TextTooLong => "text_too_long",
+            ErrorCode::TextNotAllowed => "text_not_allowed",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::GameNotFound => "game_not_found",
+            ErrorCode::NotInGame => "not_in_game",
+            ErrorCode::GameAlreadyStarted => "game_already_started",
+            ErrorCode::GameNotOver => "game_not_over",
+            ErrorCode::NotInTeam => "not_in_team",
+            ErrorCode::ChannelNotAllowed => "channel_not_allowed",
+            ErrorCode::BotsCannotSpectate => "bots_cannot_spectate",
+            ErrorCode::NotYourTurn => "not_your_turn",
+            ErrorCode::CodenameNotSingleWord => "codename_not_single_word",
+            ErrorCode::CodenameOnBoard => "codename_on_board",
+            ErrorCode::CardNotRevealable => "card_not_revealable",
+            ErrorCode::GuessRequired => "guess_required",
+            ErrorCode::AccountsDisabled => "accounts_disabled",
+            ErrorCode::InvalidUsername => "invalid_username",
+            ErrorCode::UsernameTaken => "username_taken",
+            ErrorCode::PasswordTooShort => "password_too_short",
+            ErrorCode::InvalidEmail => "invalid_email",
+            ErrorCode::PasswordOrEmailRequired => "password_or_email_required",
+            ErrorCode::InvalidCredentials => "invalid_credentials",
+            ErrorCode::ServerError => "server_error",
+        }
+    }
+
+    /// Looks up a code by name.
+    pub fn from_name(name: &str) -> Option<ErrorCode> {
+        Some(match name {
+            "unknown" => ErrorCode::Unknown,
+            "invalid_frame" => ErrorCode::InvalidFrame,
+            "encoding_not_negotiated" => ErrorCode::EncodingNotNegotiated,
+            "malformed_command" => ErrorCode::MalformedCommand,
+            "hello_required" => ErrorCode::HelloRequired,
+            "unsupported_version" => ErrorCode::UnsupportedVersion,
+            "already_said_hello" => ErrorCode::AlreadySaidHello,
+            "not_authenticated" => ErrorCode::NotAuthenticated,
+            "already_authenticated" => ErrorCode::AlreadyAuthenticated,
+            "player_not_found" => ErrorCode::PlayerNotFound,
+            "connection_lost" => ErrorCode::ConnectionLost,
+            "invalid_nickname_length" => ErrorCode::InvalidNicknameLength,
+            "invalid_nickname_characters" => ErrorCode::InvalidNicknameCharacters,
+            "nickname_not_allowed" => ErrorCode::NicknameNotAllowed,
+            "text_too_long" => ErrorCode::TextTooLong,
+            "text_not_allowed" => ErrorCode::TextNotAllowed,
+            "rate_limited" => ErrorCode::RateLimited,
+            "game_not_found" => ErrorCode::GameNotFound,
+            "not_in_game" => ErrorCode::NotInGame,
+            "game_already_started" => ErrorCode::GameAlreadyStarted,
+            "game_not_over" => ErrorCode::GameNotOver,
+            "not_in_team" => ErrorCode::NotInTeam,
+            "channel_not_allowed" => ErrorCode::ChannelNotAllowed,
+            "bots_cannot_spectate" => ErrorCode::BotsCannotSpectate,
+            "not_your_turn" => ErrorCode::NotYourTurn,
+            "codename_not_single_word" => ErrorCode::CodenameNotSingleWord,
+            "codename_on_board" => ErrorCode::CodenameOnBoard,
+            "card_not_revealable" => ErrorCode::CardNotRevealable,
+            "guess_required" => ErrorCode::GuessRequired,
+            "accounts_disabled" => ErrorCode::AccountsDisabled,
+            "invalid_username" => ErrorCode::InvalidUsername,
+            "username_taken" => ErrorCode::UsernameTaken,
+            "password_too_short" => ErrorCode::PasswordTooShort,
+            "invalid_email" => ErrorCode::InvalidEmail,
+            "password_or_email_required" => ErrorCode::PasswordOrEmailRequired,
+            "invalid_credentials" => ErrorCode::InvalidCredentials,
+            "server_error" => ErrorCode::ServerError,
+            _ => return None,
+        })
+    }
+
+    /// Returns the broad category of the error.
+    pub fn kind(self) -> ProtocolErrorKind {
+        match self {
+            ErrorCode::Unknown
+            | ErrorCode::PlayerNotFound
+            | ErrorCode::ConnectionLost
+            | ErrorCode::ServerError => ProtocolErrorKind::InternalError,
+            ErrorCode::InvalidFrame
+            | ErrorCode::EncodingNotNegotiated
+            | ErrorCode::MalformedCommand => ProtocolErrorKind::InvalidCommand,
+            ErrorCode::HelloRequired | ErrorCode::UnsupportedVersion => {
+                ProtocolErrorKind::UnsupportedVersion
+            }
+            ErrorCode::NotAuthenticated => ProtocolErrorKind::NotAuthenticated,
+            ErrorCode::AlreadyAuthenticated => ProtocolErrorKind::AlreadyAuthenticated,
+            ErrorCode::GameNotFound => ProtocolErrorKind::NotFound,
+            ErrorCode::AlreadySaidHello
+            | ErrorCode::NotInGame
+            | ErrorCode::GameAlreadyStarted
+            | ErrorCode::GameNotOver
+            | ErrorCode::NotInTeam
+            | ErrorCode::ChannelNotAllowed
+            | ErrorCode::NotYourTurn
+            | ErrorCode::GuessRequired
+            | ErrorCode::AccountsDisabled => ProtocolErrorKind::BadState,
+            ErrorCode::InvalidNicknameLength
+            | ErrorCode::InvalidNicknameCharacters
+            | ErrorCode::NicknameNotAllowed
+            | ErrorCode::TextTooLong
+            | ErrorCode::TextNotAllowed
+            | ErrorCode::RateLimited
+            | ErrorCode::BotsCannotSpectate
+            | ErrorCode::CodenameNotSingleWord
+            | ErrorCode::CodenameOnBoard
+            | ErrorCode::CardNotRevealable
+            | ErrorCode::InvalidUsername
+            | ErrorCode::UsernameTaken
+            | ErrorCode::PasswordTooShort
+            | ErrorCode::InvalidEmail
+            | ErrorCode::PasswordOrEmailRequired
+            | ErrorCode::InvalidCredentials => ProtocolErrorKind::BadInput,
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ErrorCode, D::Error> {
+        struct CodeVisitor;
+
+        impl<'de> Visitor<'de> for CodeVisitor {
+            type Value = ErrorCode;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an error code")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<ErrorCode, E> {
+                Ok(ErrorCode::from_name(value).unwrap_or(ErrorCode::Unknown))
+            }
+        }
+
+        deserializer.deserialize_str(CodeVisitor)
+    }
+}
+
+/// Structured information about an error.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ErrorDetails {
+    /// The name of the command field that was rejected.
+    #[serde(default)]
+    pub field: Option<String>,
+    /// The limit that was exceeded, like a maximum length.
+    #[serde(default)]
+    pub limit: Option<u64>,
+    /// Milliseconds after which the command can be retried.
+    #[serde(default)]
+    pub retry_after_ms: Option<u64>,
+    /// The protocol versions supported by the server.
+    #[serde(default)]
+    pub supported_versions: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProtocolError {
+    kind: ProtocolErrorKind,
+    #[serde(default)]
+    code: ErrorCode,
+    /// An English description of the error for logs and for clients that
+    /// do not know the code.
+    message: String,
+    #[serde(default)]
+    details: Option<Box<ErrorDetails>>,
+    /// The ID of the command that caused this error.
+    #[serde(default)]
+    request_id: Option<u64>,
+}
+
+impl ProtocolError {
+    pub fn new<S: Into<String>>(code: ErrorCode, s: S) -> ProtocolError {
+        ProtocolError {
+            kind: code.kind(),
+            code,
+            message: s.into(),
+            details: None,
+            request_id: None,
+        }
+    }
+
+    fn details_mut(&mut self) -> &mut ErrorDetails {
+        self.details.get_or_insert_with(Default::default)
+    }
+
+    /// Names the command field that was rejected.
+    pub fn with_field(mut self, field: &str) -> ProtocolError {
+        self.details_mut().field = Some(field.into());
+        self
+    }
+
+    /// Attaches the limit that was exceeded.
+    pub fn with_limit(mut self, limit: u64) -> ProtocolError {
+        self.details_mut().limit = Some(limit);
+        self
+    }
+
+    /// Attaches a hint when the command can be retried.
+    pub fn with_retry_after_ms(mut self, retry_after_ms: u64) -> ProtocolError {
+        self.details_mut().retry_after_ms = Some(retry_after_ms);
+        self
+    }
+
+    /// Attaches the protocol versions the server supports.
+    pub fn with_supported_versions(mut self, versions: Vec<u32>) -> ProtocolError {
+        self.details_mut().supported_versions = versions;
+        self
+    }
+
+    /// Attaches the ID of the command that failed.
+    pub fn with_request_id(mut self, request_id: Option<u64>) -> ProtocolError {
+        self.request_id = request_id;
+        self
+    }
+
+    pub fn kind(&self) -> ProtocolErrorKind {
+        self.kind
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn details(&self) -> Option<&ErrorDetails> {
+        self.details.as_deref()
+    }
+
+    pub fn request_id(&self) -> Option<u64> {
+        self.request_id
+    }
+}
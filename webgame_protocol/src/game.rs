@@ -60,11 +60,66 @@ impl Turn {
     }
 }
 
+/// A codename shared by a spymaster.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Clue {
+    pub team: Team,
+    pub word: String,
+    /// The number of agents the clue refers to.
+    pub number: usize,
+    /// Set if the spymaster gave unlimited instead of a number.  The number
+    /// is zero then.  As in the board game a clue of zero allows unlimited
+    /// guesses too, so the flag only changes how the clue is shown.
+    #[serde(default)]
+    pub unlimited: bool,
+    /// The number of tiles the operatives already revealed for this clue.
+    pub guesses: usize,
+}
+
+impl Clue {
+    /// Checks if the operatives may keep guessing.
+    ///
+    /// Operatives get one guess more than the number of the clue.  Clues
+    /// of zero and unlimited clues allow any number of guesses.
+    pub fn allows_more_guesses(&self) -> bool {
+        self.unlimited || self.number == 0 || self.guesses <= self.number
+    }
+}
+
+/// A tile revealed by an operative.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClueGuess {
+    pub player_id: Uuid,
+    pub index: usize,
+    pub character: Character,
+}
+
+/// A clue given during the game and the tiles revealed for it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClueLogEntry {
+    /// The spymaster who gave the clue.
+    pub player_id: Uuid,
+    pub team: Team,
+    pub word: String,
+    pub number: usize,
+    #[serde(default)]
+    pub unlimited: bool,
+    pub guesses: Vec<ClueGuess>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GameStateSnapshot {
     pub players: Vec<GamePlayerState>,
     pub tiles: Vec<Tile>,
     pub turn: Turn,
+    /// The clue the operatives are currently guessing for.
+    #[serde(default)]
+    pub clue: Option<Clue>,
+    #[serde(default)]
+    pub winner: Option<Team>,
+    /// All clues given so far, oldest first.
+    #[serde(default)]
+    pub clues: Vec<ClueLogEntry>,
 }
 
 impl Default for GameStateSnapshot {
@@ -73,6 +128,9 @@ impl Default for GameStateSnapshot {
             players: vec![],
             tiles: vec![Tile::default(); 25],
             turn: Turn::Pregame,
+            clue: None,
+            winner: None,
+            clues: vec![],
         }
     }
 }
@@ -83,6 +141,61 @@ pub struct GameInfo {
     pub join_code: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    Classic,
+}
+
+impl Default for GameMode {
+    fn default() -> GameMode {
+        GameMode::Classic
+    }
+}
+
+/// Controls how much of the key card spectators get to see.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SpectatorVisibility {
+    /// Spectators see the full key card.
+    Full,
+    /// Spectators only see tiles which were already revealed.
+    RevealedOnly,
+    /// Spectators see the full key card once the game is over.
+    Delayed,
+}
+
+impl Default for SpectatorVisibility {
+    fn default() -> SpectatorVisibility {
+        SpectatorVisibility::Full
+    }
+}
+
+/// How well a bot plays.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BotDifficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Default for BotDifficulty {
+    fn default() -> BotDifficulty {
+        BotDifficulty::Normal
+    }
+}
+
+/// An entry in the lobby listing of public games.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameListEntry {
+    pub join_code: String,
+    pub host_nickname: String,
+    pub player_count: usize,
+    pub mode: GameMode,
+    pub is_pregame: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum Team {
@@ -90,6 +203,24 @@ pub enum Team {
     Blue,
 }
 
+impl Team {
+    /// Returns the opposing team.
+    pub fn other(self) -> Team {
+        match self {
+            Team::Red => Team::Blue,
+            Team::Blue => Team::Red,
+        }
+    }
+
+    /// Returns the character of this team's agents.
+    pub fn agent(self) -> Character {
+        match self {
+            Team::Red => Character::RedAgent,
+            Team::Blue => Character::BlueAgent,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Tile {
     pub codeword: String,
@@ -137,7 +268,7 @@ pub struct GamePlayerState {
 
 impl GamePlayerState {
     pub fn get_turn_player_action(&self, turn: Turn) -> Option<PlayerAction> {
-        if self.team != turn.team() && Some(self.role) != turn.role() {
+        if self.team != turn.team() || Some(self.role) != turn.role() {
             None
         } else {
             match self.role {
@@ -148,3 +279,57 @@ impl GamePlayerState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(team: Option<Team>, role: PlayerRole) -> GamePlayerState {
+        GamePlayerState {
+            player: PlayerInfo {
+                id: Uuid::nil(),
+                nickname: "Alice".into(),
+                is_bot: false,
+                account: None,
+            },
+            team,
+            role,
+            ready: true,
+        }
+    }
+
+    #[test]
+    fn test_turn_player_action() {
+        let red_spymaster = player(Some(Team::Red), PlayerRole::Spymaster);
+        let red_operative = player(Some(Team::Red), PlayerRole::Operative);
+        let blue_operative = player(Some(Team::Blue), PlayerRole::Operative);
+        let spectator = player(None, PlayerRole::Spectator);
+
+        assert_eq!(
+            red_spymaster.get_turn_player_action(Turn::RedSpymasterThinking),
+            Some(PlayerAction::ShareCodename)
+        );
+        assert_eq!(
+            red_operative.get_turn_player_action(Turn::RedOperativesGuessing),
+            Some(PlayerAction::Guess)
+        );
+
+        // both the team and the role have to match
+        assert_eq!(
+            red_spymaster.get_turn_player_action(Turn::RedOperativesGuessing),
+            None
+        );
+        assert_eq!(
+            blue_operative.get_turn_player_action(Turn::RedOperativesGuessing),
+            None
+        );
+        assert_eq!(
+            red_operative.get_turn_player_action(Turn::BlueSpymasterThinking),
+            None
+        );
+        for &turn in [Turn::Pregame, Turn::Intermission, Turn::Endgame].iter() {
+            assert_eq!(red_operative.get_turn_player_action(turn), None);
+            assert_eq!(spectator.get_turn_player_action(turn), None);
+        }
+    }
+}
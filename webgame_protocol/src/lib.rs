@@ -1,7 +1,47 @@
+//! The messages exchanged between the webgame server and its clients.
+//!
+//! Commands are sent by clients and tagged with `cmd`, messages are sent by
+//! the server and tagged with `type`.  By default both are JSON objects in
+//! text frames.  Clients can ask for MessagePack in binary frames during
+//! the handshake which makes game state snapshots considerably smaller.
+//!
+//! # Versioning
+//!
+//! A client starts every connection with `Command::Hello` carrying the
+//! [`PROTOCOL_VERSION`] it was built against.  The server answers with
+//! `Message::Hello` which lists the versions and features it supports, or
+//! with an `unsupported_version` error in which case the client should ask
+//! the user to reload.  Commands other than `hello` are rejected until the
+//! handshake happened.
+//!
+//! Changes follow these rules:
+//!
+//! - New fields must be optional (`Option` or `#[serde(default)]`) so that
+//!   peers that do not know about them keep working.  Unknown fields are
+//!   ignored by both sides.
+//! - New messages can be added at any time.  Clients must ignore messages
+//!   they cannot decode.
+//! - New commands must be announced with a feature in `Message::Hello` and
+//!   clients only send them if the server listed the feature.
+//! - Renaming or removing fields or variants, or changing their types,
+//!   requires a new protocol version.  The server keeps supporting older
+//!   versions for as long as they are listed in `supported_versions`.
+//!
+//! # Errors
+//!
+//! Failed commands are answered with `Message::Error`.  Besides the broad
+//! `kind` every error carries a stable `code` and optional `details`.
+//! Clients should show messages based on the code.  The English `message`
+//! is meant for logs and as a fallback for codes added after the client
+//! was built.  Existing codes are never renamed or reused.
+//!
+//! [`PROTOCOL_VERSION`]: constant.PROTOCOL_VERSION.html
+mod error;
 mod game;
 mod message;
 mod player;
 
+pub use crate::error::*;
 pub use crate::game::*;
 pub use crate::message::*;
 pub use crate::player::*;
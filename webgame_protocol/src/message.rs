@@ -1,80 +1,208 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::game::{GameInfo, GamePlayerState, GameStateSnapshot, PlayerRole, Team};
-use crate::player::PlayerInfo;
+use crate::error::ProtocolError;
+use crate::game::{
+    BotDifficulty, GameInfo, GameListEntry, GameMode, GamePlayerState, GameStateSnapshot,
+    PlayerRole, SpectatorVisibility, Team,
+};
+use crate::player::{LeaderboardEntry, PlayerInfo, PlayerStats};
+
+/// The version of the protocol implemented by this crate.
+pub const PROTOCOL_VERSION: u32 = 1;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "cmd", rename_all = "snake_case")]
 pub enum Command {
+    Hello(HelloCommand),
     Authenticate(AuthenticateCommand),
     SendText(SendTextCommand),
     ShareCodename(ShareCodenameCommand),
-    NewGame,
+    NewGame(NewGameCommand),
+    ListGames,
     JoinGame(JoinGameCommand),
     LeaveGame,
     MarkReady,
     SetPlayerRole(SetPlayerRoleCommand),
     SetPlayerTeam(SetPlayerTeamCommand),
     RevealCard(RevealCardCommand),
+    EndTurn,
+    AddBot(AddBotCommand),
+    Register(RegisterCommand),
+    RequestMagicToken(RequestMagicTokenCommand),
+    /// Asks for `Message::Stats`.  Only available if the server announced
+    /// the `stats` feature.
+    GetStats,
+    /// Starts a new round with the same teams once the game is over.
+    Rematch,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Hash)]
-#[serde(rename_all = "snake_case")]
-pub enum ProtocolErrorKind {
-    /// Client tried to authenticate twice
-    AlreadyAuthenticated,
-    /// Tried to do something while unauthenticated
-    NotAuthenticated,
-    /// Client sent in some garbage
-    InvalidCommand,
-    /// Cannot be done at this time
-    BadState,
-    /// Something wasn't found
-    NotFound,
-    /// Invalid input.
-    BadInput,
-    /// This should never happen.
-    InternalError,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ProtocolError {
-    kind: ProtocolErrorKind,
-    message: String,
-}
-
-impl ProtocolError {
-    pub fn new<S: Into<String>>(kind: ProtocolErrorKind, s: S) -> ProtocolError {
-        ProtocolError {
-            kind,
-            message: s.into(),
+/// A command with an optional ID to match it with the server's response.
+///
+/// On the wire the command is nested below the `command` key:
+/// `{"request_id": 1, "command": {"cmd": "mark_ready"}}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommandEnvelope {
+    /// If set the server answers with a `Message::Ack` or a
+    /// `Message::Error` carrying the same ID once the command was handled.
+    #[serde(default)]
+    pub request_id: Option<u64>,
+    pub command: Command,
+}
+
+impl From<Command> for CommandEnvelope {
+    fn from(command: Command) -> CommandEnvelope {
+        CommandEnvelope {
+            request_id: None,
+            command,
         }
     }
+}
+
+/// How commands and messages are encoded on the websocket.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// JSON in text frames.
+    Json,
+    /// MessagePack with named fields in binary frames.
+    Msgpack,
+}
 
-    pub fn kind(&self) -> ProtocolErrorKind {
-        self.kind
+impl Default for Encoding {
+    fn default() -> Encoding {
+        Encoding::Json
+    }
+}
+
+impl Encoding {
+    /// Returns the name of the encoding as used in `HelloCommand`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+            Encoding::Msgpack => "msgpack",
+        }
     }
 
-    pub fn message(&self) -> &str {
-        &self.message
+    /// Looks up an encoding by name.
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "json" => Some(Encoding::Json),
+            "msgpack" => Some(Encoding::Msgpack),
+            _ => None,
+        }
     }
 }
 
+/// The first command sent on a new connection.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HelloCommand {
+    pub protocol_version: u32,
+    /// A name and version identifying the client for logging.
+    #[serde(default)]
+    pub client_name: String,
+    /// The encodings the client accepts in order of preference.
+    ///
+    /// These are names rather than `Encoding` values so that servers do
+    /// not reject clients offering encodings they do not know about.  The
+    /// server falls back to JSON if it supports none of them.
+    #[serde(default)]
+    pub encodings: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AuthenticateCommand {
     pub nickname: String,
+    /// An optional token identifying the browser across tabs.
+    #[serde(default)]
+    pub session_token: Option<String>,
+    /// Signs in to an account instead of playing as a guest.
+    ///
+    /// The nickname can be left empty in which case the nickname used
+    /// last time with this account is picked.
+    #[serde(default)]
+    pub credentials: Option<Credentials>,
+}
+
+/// Proves the ownership of an account.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Credentials {
+    Password {
+        username: String,
+        password: String,
+    },
+    /// A single use token requested with `Command::RequestMagicToken`.
+    MagicToken {
+        token: String,
+    },
+}
+
+/// Creates an account.
+///
+/// Only available if the server announced the `accounts` feature.  The
+/// account needs a password, an email address for sign-in tokens or both.
+/// Registering does not sign in, that is done with `Command::Authenticate`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RegisterCommand {
+    pub username: String,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// Mails a sign-in token to the email address of an account.
+///
+/// The server does not tell if the account exists.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RequestMagicTokenCommand {
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatChannel {
+    /// Everybody in the game.
+    All,
+    /// Only the players on the sender's team.
+    Team,
+    /// Only the spectators.
+    Spectators,
+}
+
+impl Default for ChatChannel {
+    fn default() -> ChatChannel {
+        ChatChannel::All
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SendTextCommand {
     pub text: String,
+    #[serde(default)]
+    pub channel: ChatChannel,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ShareCodenameCommand {
     pub codename: String,
     pub number: usize,
+    /// Gives unlimited instead of a number.  Operatives can guess as often
+    /// as for a clue of zero, only the clue is shown differently.
+    #[serde(default)]
+    pub unlimited: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NewGameCommand {
+    /// Public games are shown in the lobby listing.
+    #[serde(default)]
+    pub public: bool,
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub spectator_visibility: SpectatorVisibility,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -97,27 +225,155 @@ pub struct RevealCardCommand {
     pub index: usize,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddBotCommand {
+    pub team: Team,
+    pub role: PlayerRole,
+    #[serde(default)]
+    pub difficulty: BotDifficulty,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Message {
+    Hello(HelloMessage),
+    Ack(AckMessage),
     Chat(ChatMessage),
     PlayerConnected(GamePlayerState),
     PlayerDisconnected(PlayerDisconnectedMessage),
     PregameStarted,
     GameJoined(GameInfo),
     GameLeft,
+    GameList(GameListMessage),
     Authenticated(PlayerInfo),
     Error(ProtocolError),
     GameStateSnapshot(GameStateSnapshot),
+    ServerNotice(ServerNoticeMessage),
+    ServerShutdown(ServerShutdownMessage),
+    Stats(StatsMessage),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ChatMessage {
     pub player_id: Uuid,
     pub text: String,
+    #[serde(default)]
+    pub channel: ChatChannel,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PlayerDisconnectedMessage {
     pub player_id: Uuid,
 }
+
+/// The server's answer to `Command::Hello`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HelloMessage {
+    /// The protocol version used for this connection.
+    pub protocol_version: u32,
+    /// All protocol versions the server supports.
+    pub supported_versions: Vec<u32>,
+    /// Optional features enabled on this server.
+    ///
+    /// These are plain strings so that clients do not fail on features
+    /// that were added after they were built.
+    #[serde(default)]
+    pub features: Vec<String>,
+    /// The encoding the server picked from the client's list.
+    ///
+    /// Text frames are always JSON and binary frames are always
+    /// MessagePack, so both sides can decode frames by their type.
+    #[serde(default)]
+    pub encoding: Encoding,
+}
+
+/// Confirms that a command with a request ID was handled.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AckMessage {
+    pub request_id: u64,
+}
+
+/// A notice from the server operators shown to all players.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServerNoticeMessage {
+    pub text: String,
+}
+
+/// Sent to all players before the server shuts down.
+///
+/// The connection is closed by the server shortly afterwards.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ServerShutdownMessage {
+    pub reason: String,
+    /// Seconds after which clients can try to reconnect if known.
+    #[serde(default)]
+    pub reconnect_after: Option<u64>,
+}
+
+/// The answer to `Command::GetStats`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatsMessage {
+    /// The player's own statistics if they signed in to an account.
+    #[serde(default)]
+    pub stats: Option<PlayerStats>,
+    /// The players with the most wins.
+    #[serde(default)]
+    pub leaderboard: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameListMessage {
+    pub games: Vec<GameListEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelopes() -> Vec<CommandEnvelope> {
+        vec![
+            Command::MarkReady.into(),
+            CommandEnvelope {
+                request_id: Some(7),
+                command: Command::RevealCard(RevealCardCommand { index: 3 }),
+            },
+            CommandEnvelope {
+                request_id: Some(u64::MAX),
+                command: Command::SendText(SendTextCommand {
+                    text: "hello".into(),
+                    channel: ChatChannel::Team,
+                }),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_envelope_json() {
+        let envelope = CommandEnvelope {
+            request_id: Some(7),
+            command: Command::RevealCard(RevealCardCommand { index: 3 }),
+        };
+        assert_eq!(
+            serde_json::to_value(&envelope).unwrap(),
+            serde_json::json!({"request_id": 7, "command": {"cmd": "reveal_card", "index": 3}})
+        );
+        let envelope: CommandEnvelope =
+            serde_json::from_str(r#"{"command": {"cmd": "mark_ready"}}"#).unwrap();
+        assert_eq!(envelope.request_id, None);
+
+        for envelope in envelopes() {
+            let json = serde_json::to_string(&envelope).unwrap();
+            let decoded: CommandEnvelope = serde_json::from_str(&json).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", envelope));
+        }
+    }
+
+    #[test]
+    fn test_envelope_msgpack() {
+        for envelope in envelopes() {
+            let bytes = rmp_serde::to_vec_named(&envelope).unwrap();
+            let decoded: CommandEnvelope = rmp_serde::from_read_ref(&bytes).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", envelope));
+        }
+    }
+}
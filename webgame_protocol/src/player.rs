@@ -5,4 +5,63 @@ use uuid::Uuid;
 pub struct PlayerInfo {
     pub id: Uuid,
     pub nickname: String,
+    /// Set for players controlled by the server.
+    #[serde(default)]
+    pub is_bot: bool,
+    /// The username of the account the player signed in to.
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// Statistics of a player with an account across all finished games.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub games_played_spymaster: u32,
+    pub games_won_spymaster: u32,
+    pub games_played_operative: u32,
+    pub games_won_operative: u32,
+    /// How often the player revealed the assassin.
+    pub assassin_hits: u32,
+    /// The number of clues the player gave as spymaster.
+    pub clues_given: u32,
+    /// The number of cards revealed for the player's clues.
+    pub clue_guesses: u32,
+    /// The number of clues for which the operatives found at least as many
+    /// of their agents as the clue's number.
+    pub successful_clues: u32,
+}
+
+impl PlayerStats {
+    pub fn games_played(&self) -> u32 {
+        self.games_played_spymaster + self.games_played_operative
+    }
+
+    pub fn games_won(&self) -> u32 {
+        self.games_won_spymaster + self.games_won_operative
+    }
+
+    /// Returns how many cards operatives revealed per clue on average.
+    pub fn average_guesses_per_clue(&self) -> Option<f64> {
+        if self.clues_given == 0 {
+            None
+        } else {
+            Some(f64::from(self.clue_guesses) / f64::from(self.clues_given))
+        }
+    }
+
+    /// Returns the share of clues that were successful.
+    pub fn clue_success_rate(&self) -> Option<f64> {
+        if self.clues_given == 0 {
+            None
+        } else {
+            Some(f64::from(self.successful_clues) / f64::from(self.clues_given))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub username: String,
+    pub stats: PlayerStats,
 }
@@ -0,0 +1,483 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::config::AccountsConfig;
+use crate::protocol::{ErrorCode, LeaderboardEntry, PlayerStats, ProtocolError};
+
+const SCHEMA: &str = "
+    create table if not exists accounts (
+        username text primary key collate nocase,
+        password_hash text,
+        email text,
+        nickname text,
+        created_at integer not null
+    );
+    create table if not exists magic_tokens (
+        token_hash text primary key,
+        username text not null references accounts(username),
+        expires_at integer not null
+    );
+    create table if not exists player_stats (
+        username text primary key collate nocase references accounts(username),
+        games_played_spymaster integer not null default 0,
+        games_won_spymaster integer not null default 0,
+        games_played_operative integer not null default 0,
+        games_won_operative integer not null default 0,
+        assassin_hits integer not null default 0,
+        clues_given integer not null default 0,
+        clue_guesses integer not null default 0,
+        successful_clues integer not null default 0
+    );
+";
+
+const STATS_COLUMNS: &str = "games_played_spymaster, games_won_spymaster,
+    games_played_operative, games_won_operative, assassin_hits, clues_given,
+    clue_guesses, successful_clues";
+
+pub const MAX_USERNAME_LENGTH: usize = 32;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Delivers mails to players.
+pub trait Mailer: Send + Sync {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), io::Error>;
+}
+
+/// A stand-in mailer which writes every mail to a file in a directory.
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: PathBuf) -> FileMailer {
+        FileMailer { dir }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), io::Error> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self
+            .dir
+            .join(format!("{}-{}.txt", unix_time(), Uuid::new_v4()));
+        fs::write(
+            path,
+            format!("To: {}\nSubject: {}\n\n{}\n", to, subject, body),
+        )
+    }
+}
+
+/// An account as stored in the database.
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub username: String,
+    /// The nickname used the last time the player signed in.
+    pub nickname: Option<String>,
+}
+
+/// The accounts of registered players and their statistics, stored in
+/// SQLite.
+pub struct AccountStore {
+    conn: Mutex<Connection>,
+    mailer: Box<dyn Mailer>,
+    magic_token_ttl: Duration,
+}
+
+fn unix_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn server_error(err: rusqlite::Error) -> ProtocolError {
+    log::error!("account database error: {}", err);
+    ProtocolError::new(ErrorCode::ServerError, "internal server error")
+}
+
+/// Reads the stats columns starting at `offset`.
+fn read_stats(row: &Row, offset: usize) -> Result<PlayerStats, rusqlite::Error> {
+    Ok(PlayerStats {
+        games_played_spymaster: row.get(offset)?,
+        games_won_spymaster: row.get(offset + 1)?,
+        games_played_operative: row.get(offset + 2)?,
+        games_won_operative: row.get(offset + 3)?,
+        assassin_hits: row.get(offset + 4)?,
+        clues_given: row.get(offset + 5)?,
+        clue_guesses: row.get(offset + 6)?,
+        successful_clues: row.get(offset + 7)?,
+    })
+}
+
+fn invalid_credentials() -> ProtocolError {
+    ProtocolError::new(
+        ErrorCode::InvalidCredentials,
+        "invalid username, password or token",
+    )
+}
+
+fn hash_password(password: &str) -> Result<String, ProtocolError> {
+    let salt: [u8; 16] = thread_rng().gen();
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        ..argon2::Config::default()
+    };
+    argon2::hash_encoded(password.as_bytes(), &salt, &config).map_err(|err| {
+        log::error!("could not hash password: {}", err);
+        ProtocolError::new(ErrorCode::ServerError, "internal server error")
+    })
+}
+
+/// Hashes a sign-in token for storage.
+///
+/// Tokens are long random strings so unlike passwords they do not need a
+/// slow hash.
+fn hash_token(token: &str) -> String {
+    blake2b_simd::blake2b(token.as_bytes()).to_hex().to_string()
+}
+
+lazy_static! {
+    /// A hash of a random password that is checked for accounts without
+    /// one, so that unknown usernames take as long as wrong passwords.
+    static ref DUMMY_PASSWORD_HASH: String =
+        hash_password(&Uuid::new_v4().to_string()).unwrap_or_default();
+}
+
+fn is_valid_username(username: &str) -> bool {
+    username.len() >= 3
+        && username.len() <= MAX_USERNAME_LENGTH
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+impl AccountStore {
+    /// Opens the account database from the config.
+    ///
+    /// Returns `None` if accounts are not configured.
+    pub fn from_config(config: &AccountsConfig) -> Result<Option<AccountStore>, io::Error> {
+        let path = match config.database {
+            Some(ref path) => path,
+            None => return Ok(None),
+        };
+        let conn = Connection::open(path)
+            .and_then(|conn| conn.execute_batch(SCHEMA).map(|_| conn))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(Some(AccountStore {
+            conn: Mutex::new(conn),
+            mailer: Box::new(FileMailer::new(config.mail_dir.clone())),
+            magic_token_ttl: Duration::from_secs(config.magic_token_ttl_secs),
+        }))
+    }
+
+    /// Creates an account.
+    ///
+    /// Hashing the password is slow so this should not be called on the
+    /// async executor.
+    pub fn register(
+        &self,
+        username: &str,
+        password: Option<&str>,
+        email: Option<&str>,
+    ) -> Result<Account, ProtocolError> {
+        if !is_valid_username(username) {
+            return Err(ProtocolError::new(
+                ErrorCode::InvalidUsername,
+                format!(
+                    "username must be between 3 and {} letters, digits, '-', '_' or '.'",
+                    MAX_USERNAME_LENGTH
+                ),
+            )
+            .with_field("username")
+            .with_limit(MAX_USERNAME_LENGTH as u64));
+        }
+        if password.is_none() && email.is_none() {
+            return Err(ProtocolError::new(
+                ErrorCode::PasswordOrEmailRequired,
+                "account needs a password or an email address",
+            ));
+        }
+        if let Some(email) = email {
+            let valid = match email.find('@') {
+                Some(idx) => idx > 0 && idx + 1 < email.len(),
+                None => false,
+            };
+            if !valid || email.chars().any(char::is_whitespace) {
+                return Err(
+                    ProtocolError::new(ErrorCode::InvalidEmail, "invalid email address")
+                        .with_field("email"),
+                );
+            }
+        }
+        let password_hash = match password {
+            Some(password) if password.chars().count() < MIN_PASSWORD_LENGTH => {
+                return Err(ProtocolError::new(
+                    ErrorCode::PasswordTooShort,
+                    format!(
+                        "password must be at least {} characters",
+                        MIN_PASSWORD_LENGTH
+                    ),
+                )
+                .with_field("password")
+                .with_limit(MIN_PASSWORD_LENGTH as u64));
+            }
+            Some(password) => Some(hash_password(password)?),
+            None => None,
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let exists = conn
+            .query_row(
+                "select 1 from accounts where username = ?",
+                params![username],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(server_error)?
+            .is_some();
+        if exists {
+            return Err(ProtocolError::new(
+                ErrorCode::UsernameTaken,
+                "this username is already taken",
+            )
+            .with_field("username"));
+        }
+        conn.execute(
+            "insert into accounts (username, password_hash, email, created_at)
+             values (?, ?, ?, ?)",
+            params![username, password_hash, email, unix_time()],
+        )
+        .map_err(server_error)?;
+        log::info!("registered account {:?}", username);
+        Ok(Account {
+            username: username.into(),
+            nickname: None,
+        })
+    }
+
+    /// Looks up an account by username and password.
+    ///
+    /// Like `register` this hashes the password.
+    pub fn sign_in_with_password(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Account, ProtocolError> {
+        let row: Option<(String, Option<String>, Option<String>)> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "select username, password_hash, nickname from accounts where username = ?",
+                params![username],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(server_error)?;
+        let (account, hash) = match row {
+            Some((username, hash, nickname)) => (Some(Account { username, nickname }), hash),
+            None => (None, None),
+        };
+        let is_valid = argon2::verify_encoded(
+            hash.as_deref().unwrap_or(&DUMMY_PASSWORD_HASH),
+            password.as_bytes(),
+        )
+        .unwrap_or(false);
+        match account {
+            Some(account) if is_valid && hash.is_some() => Ok(account),
+            _ => Err(invalid_credentials()),
+        }
+    }
+
+    /// Mails a sign-in token to the account's email address.
+    ///
+    /// Nothing happens for unknown accounts or accounts without an email
+    /// address.
+    pub fn send_magic_token(&self, username: &str) -> Result<(), ProtocolError> {
+        let conn = self.conn.lock().unwrap();
+        let row: Option<(String, Option<String>)> = conn
+            .query_row(
+                "select username, email from accounts where username = ?",
+                params![username],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(server_error)?;
+        let (username, email) = match row {
+            Some((username, Some(email))) => (username, email),
+            _ => return Ok(()),
+        };
+
+        let now = unix_time();
+        let token: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+        conn.execute(
+            "delete from magic_tokens where expires_at <= ?",
+            params![now],
+        )
+        .map_err(server_error)?;
+        conn.execute(
+            "insert into magic_tokens (token_hash, username, expires_at) values (?, ?, ?)",
+            params![
+                hash_token(&token),
+                &username,
+                now + self.magic_token_ttl.as_secs() as i64
+            ],
+        )
+        .map_err(server_error)?;
+
+        self.mailer
+            .send(
+                &email,
+                "Your sign-in token",
+                &format!(
+                    "Hello {},\n\nuse this token to sign in: {}\n\nIt expires in {} minutes.",
+                    username,
+                    token,
+                    self.magic_token_ttl.as_secs() / 60
+                ),
+            )
+            .map_err(|err| {
+                log::error!("could not send sign-in token: {}", err);
+                ProtocolError::new(ErrorCode::ServerError, "internal server error")
+            })
+    }
+
+    /// Looks up the account of a sign-in token without using it up.
+    pub fn find_magic_token(&self, token: &str) -> Result<Account, ProtocolError> {
+        let row: Option<(String, Option<String>)> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "select accounts.username, accounts.nickname
+                   from magic_tokens join accounts using (username)
+                  where magic_tokens.token_hash = ? and magic_tokens.expires_at > ?",
+                params![hash_token(token), unix_time()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(server_error)?;
+        let (username, nickname) = row.ok_or_else(invalid_credentials)?;
+        Ok(Account { username, nickname })
+    }
+
+    /// Uses up a sign-in token.
+    ///
+    /// Fails if the token was already used or expired.
+    pub fn redeem_magic_token(&self, token: &str) -> Result<(), ProtocolError> {
+        let deleted = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "delete from magic_tokens where token_hash = ? and expires_at > ?",
+                params![hash_token(token), unix_time()],
+            )
+            .map_err(server_error)?;
+        if deleted == 0 {
+            return Err(invalid_credentials());
+        }
+        Ok(())
+    }
+
+    /// Adds the statistics of a finished game to the players' accounts.
+    pub fn record_stats(&self, records: &[(String, PlayerStats)]) -> Result<(), ProtocolError> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(server_error)?;
+        for (username, stats) in records {
+            tx.execute(
+                "insert or ignore into player_stats (username) values (?)",
+                params![username],
+            )
+            .map_err(server_error)?;
+            tx.execute(
+                "update player_stats set
+                    games_played_spymaster = games_played_spymaster + ?,
+                    games_won_spymaster = games_won_spymaster + ?,
+                    games_played_operative = games_played_operative + ?,
+                    games_won_operative = games_won_operative + ?,
+                    assassin_hits = assassin_hits + ?,
+                    clues_given = clues_given + ?,
+                    clue_guesses = clue_guesses + ?,
+                    successful_clues = successful_clues + ?
+                 where username = ?",
+                params![
+                    stats.games_played_spymaster,
+                    stats.games_won_spymaster,
+                    stats.games_played_operative,
+                    stats.games_won_operative,
+                    stats.assassin_hits,
+                    stats.clues_given,
+                    stats.clue_guesses,
+                    stats.successful_clues,
+                    username
+                ],
+            )
+            .map_err(server_error)?;
+        }
+        tx.commit().map_err(server_error)
+    }
+
+    /// Returns the statistics of an account.
+    pub fn get_stats(&self, username: &str) -> Result<PlayerStats, ProtocolError> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!(
+                    "select {} from player_stats where username = ?",
+                    STATS_COLUMNS
+                ),
+                params![username],
+                |row| read_stats(row, 0),
+            )
+            .optional()
+            .map(Option::unwrap_or_default)
+            .map_err(server_error)
+    }
+
+    /// Returns the players with the most wins.
+    pub fn leaderboard(&self, limit: usize) -> Result<Vec<LeaderboardEntry>, ProtocolError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(&format!(
+                "select username, {} from player_stats
+                  order by games_won_spymaster + games_won_operative desc,
+                           games_played_spymaster + games_played_operative asc,
+                           username
+                  limit ?",
+                STATS_COLUMNS
+            ))
+            .map_err(server_error)?;
+        let rows = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(LeaderboardEntry {
+                    username: row.get(0)?,
+                    stats: read_stats(row, 1)?,
+                })
+            })
+            .map_err(server_error)?;
+        rows.collect::<Result<_, _>>().map_err(server_error)
+    }
+
+    /// Remembers the nickname for the next time the player signs in.
+    pub fn set_nickname(&self, username: &str, nickname: &str) -> Result<(), ProtocolError> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "update accounts set nickname = ? where username = ?",
+                params![nickname, username],
+            )
+            .map(|_| ())
+            .map_err(server_error)
+    }
+}
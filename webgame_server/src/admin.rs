@@ -0,0 +1,242 @@
+//! The HTTP API for server operators.
+//!
+//! All routes live below `/admin` and require the admin token from the
+//! config to be passed as bearer token.
+use std::cmp::Reverse;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use warp::http::StatusCode;
+use warp::{Filter, Rejection, Reply};
+
+use crate::game::Game;
+use crate::protocol::{
+    Clue, GameMode, GamePlayerState, Message, ServerNoticeMessage, Team, Tile, Turn,
+};
+use crate::universe::Universe;
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Serialize)]
+struct AdminGameEntry {
+    game_id: Uuid,
+    join_code: String,
+    public: bool,
+    mode: GameMode,
+    players: Vec<GamePlayerState>,
+    turn: Turn,
+    age_secs: u64,
+}
+
+#[derive(Serialize)]
+struct AdminGameDetails {
+    #[serde(flatten)]
+    game: AdminGameEntry,
+    tiles: Vec<Tile>,
+    clue: Option<Clue>,
+    winner: Option<Team>,
+}
+
+#[derive(Deserialize)]
+struct NoticeRequest {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: &'static str,
+}
+
+#[derive(Serialize)]
+struct OkResponse {
+    ok: bool,
+}
+
+fn ok_reply() -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(warp::reply::json(&OkResponse { ok: true }), StatusCode::OK)
+}
+
+/// Compares two tokens in constant time.
+fn is_same_token(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn check_token(
+    authorization: Option<String>,
+    universe: Arc<Universe>,
+) -> Result<Arc<Universe>, Rejection> {
+    let token = match universe.config().admin.token {
+        Some(ref token) if !token.is_empty() => token,
+        // without a token the admin API does not exist.
+        _ => return Err(warp::reject::not_found()),
+    };
+    let given = authorization
+        .as_ref()
+        .filter(|x| x.starts_with("Bearer "))
+        .map(|x| &x["Bearer ".len()..])
+        .unwrap_or("");
+    if is_same_token(given.as_bytes(), token.as_bytes()) {
+        Ok(universe)
+    } else {
+        Err(warp::reject::custom(Unauthorized))
+    }
+}
+
+async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        Ok(error_reply(StatusCode::UNAUTHORIZED, "invalid admin token"))
+    } else {
+        Err(rejection)
+    }
+}
+
+fn error_reply(
+    status: StatusCode,
+    error: &'static str,
+) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(warp::reply::json(&ErrorResponse { error }), status)
+}
+
+async fn game_entry(game: &Game) -> AdminGameEntry {
+    let snapshot = game.full_snapshot().await;
+    AdminGameEntry {
+        game_id: game.id(),
+        join_code: game.join_code().to_string(),
+        public: game.is_public(),
+        mode: game.mode(),
+        players: snapshot.players,
+        turn: snapshot.turn,
+        age_secs: game.age().as_secs(),
+    }
+}
+
+async fn on_list_games(universe: Arc<Universe>) -> Result<impl Reply, Infallible> {
+    let mut games = vec![];
+    for game in universe.get_games().await {
+        games.push(game_entry(&game).await);
+    }
+    games.sort_by_key(|x| Reverse(x.age_secs));
+    Ok(warp::reply::json(&games))
+}
+
+async fn on_get_game(join_code: String, universe: Arc<Universe>) -> Result<impl Reply, Infallible> {
+    let game = match universe.get_game_by_join_code(&join_code).await {
+        Some(game) => game,
+        None => return Ok(error_reply(StatusCode::NOT_FOUND, "game not found")),
+    };
+    let snapshot = game.full_snapshot().await;
+    let details = AdminGameDetails {
+        game: game_entry(&game).await,
+        tiles: snapshot.tiles,
+        clue: snapshot.clue,
+        winner: snapshot.winner,
+    };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&details),
+        StatusCode::OK,
+    ))
+}
+
+async fn on_close_game(
+    join_code: String,
+    universe: Arc<Universe>,
+) -> Result<impl Reply, Infallible> {
+    let game = match universe.get_game_by_join_code(&join_code).await {
+        Some(game) => game,
+        None => return Ok(error_reply(StatusCode::NOT_FOUND, "game not found")),
+    };
+    log::info!("admin closed game {}", game.id());
+    game.close().await;
+    Ok(ok_reply())
+}
+
+async fn on_kick_player(
+    player_id: Uuid,
+    universe: Arc<Universe>,
+) -> Result<impl Reply, Infallible> {
+    if universe.get_player_info(player_id).await.is_none() {
+        return Ok(error_reply(StatusCode::NOT_FOUND, "player not found"));
+    }
+    log::info!("admin kicked player {}", player_id);
+    universe
+        .send(
+            player_id,
+            &Message::ServerNotice(ServerNoticeMessage {
+                text: "You were removed from the server by an administrator.".into(),
+            }),
+        )
+        .await;
+    universe.remove_player_from_game(player_id).await;
+    // dropping the player's sender closes the websocket.
+    universe.remove_player(player_id).await;
+    Ok(ok_reply())
+}
+
+async fn on_notice(
+    universe: Arc<Universe>,
+    request: NoticeRequest,
+) -> Result<impl Reply, Infallible> {
+    log::info!("admin notice: {}", &request.text);
+    universe
+        .broadcast(&Message::ServerNotice(ServerNoticeMessage {
+            text: request.text,
+        }))
+        .await;
+    Ok(ok_reply())
+}
+
+/// Returns the filter for all admin routes.
+pub fn routes(
+    universe: Arc<Universe>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let admin = warp::header::optional::<String>("authorization")
+        .and(warp::any().map(move || universe.clone()))
+        .and_then(check_token);
+
+    let list_games = warp::path("games")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(admin.clone())
+        .and_then(on_list_games);
+    let get_game = warp::path("games")
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(admin.clone())
+        .and_then(on_get_game);
+    let close_game = warp::path("games")
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(admin.clone())
+        .and_then(on_close_game);
+    let kick_player = warp::path("players")
+        .and(warp::path::param())
+        .and(warp::path("kick"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(admin.clone())
+        .and_then(on_kick_player);
+    let notice = warp::path("notice")
+        .and(warp::path::end())
+        .and(warp::post())
+        // the token is checked before the body is looked at.
+        .and(admin)
+        .and(warp::body::json())
+        .and_then(on_notice);
+
+    warp::path("admin")
+        .and(
+            list_games
+                .or(get_game)
+                .or(close_game)
+                .or(kick_player)
+                .or(notice),
+        )
+        .recover(handle_rejection)
+}
@@ -67,6 +67,33 @@ impl Board {
             .collect()
     }
 
+    /// Checks if a word is on the board and was not spotted yet.
+    pub fn is_unspotted_codeword(&self, word: &str) -> bool {
+        self.tiles
+            .iter()
+            .any(|tile| !tile.spotted && tile.codeword.eq_ignore_ascii_case(word))
+    }
+
+    /// Spots the tile at the given index and returns its character.
+    ///
+    /// Returns `None` if there is no such tile or it was already spotted.
+    pub fn spot(&mut self, index: usize) -> Option<Character> {
+        let tile = self.tiles.get_mut(index)?;
+        if tile.spotted {
+            return None;
+        }
+        tile.spotted = true;
+        Some(tile.character)
+    }
+
+    /// Returns the number of agents of a team that were not spotted yet.
+    pub fn agents_left(&self, team: Team) -> usize {
+        self.tiles
+            .iter()
+            .filter(|tile| !tile.spotted && tile.character == team.agent())
+            .count()
+    }
+
     /// Returns the initial turn
     pub fn initial_turn(&self) -> Turn {
         match self.starting_team {
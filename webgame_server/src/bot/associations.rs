@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref ASSOCIATIONS: Associations =
+        Associations::parse(include_str!("../associations.txt"));
+}
+
+/// An offline word association model.
+///
+/// The model maps every word of the wordlist to a list of associated words
+/// ordered by how strongly they are associated.
+pub struct Associations {
+    words: HashMap<String, Vec<String>>,
+}
+
+impl Associations {
+    /// Returns the associations shipped with the server.
+    pub fn get() -> &'static Associations {
+        &ASSOCIATIONS
+    }
+
+    /// Parses associations in the format of `associations.txt`.
+    pub fn parse(source: &str) -> Associations {
+        let words = source
+            .lines()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty() && !x.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ':');
+                let word = parts.next()?.trim().to_lowercase();
+                let associated = parts
+                    .next()?
+                    .split_whitespace()
+                    .map(|x| x.to_lowercase())
+                    .collect();
+                Some((word, associated))
+            })
+            .collect();
+        Associations { words }
+    }
+
+    /// Returns the words associated with a word.
+    pub fn associated_words(&self, word: &str) -> &[String] {
+        self.words
+            .get(&word.to_lowercase())
+            .map(|x| x.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns how strongly a clue relates to a word between 0 and 1.
+    pub fn score(&self, clue: &str, word: &str) -> f32 {
+        let clue = clue.to_lowercase();
+        let word = word.to_lowercase();
+        if clue == word {
+            return 1.0;
+        }
+
+        let associated = self.associated_words(&word);
+        if let Some(idx) = associated.iter().position(|x| *x == clue) {
+            return (1.0 - 0.07 * idx as f32).max(0.5);
+        }
+
+        // the clue might be a word with associations of its own.
+        let clue_associated = self.associated_words(&clue);
+        if clue_associated.contains(&word) {
+            return 0.7;
+        }
+        let shared = clue_associated
+            .iter()
+            .filter(|x| associated.contains(x))
+            .count();
+        if shared > 0 {
+            return (0.2 + 0.1 * shared as f32).min(0.5);
+        }
+
+        // related word forms such as plurals.
+        if (word.len() >= 4 && clue.starts_with(&word))
+            || (clue.len() >= 4 && word.starts_with(&clue))
+        {
+            return 0.6;
+        }
+
+        0.0
+    }
+}
@@ -0,0 +1,186 @@
+//! Bots are players that are controlled by the server.
+//!
+//! A bot is registered with the universe like any other player and sends
+//! the same commands a client would.  Instead of a websocket its messages
+//! go to a channel which the bot task reads from.
+
+mod associations;
+mod operative;
+mod spymaster;
+
+use std::sync::Arc;
+
+use rand::thread_rng;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use warp::ws;
+
+use crate::bot::associations::Associations;
+use crate::protocol::{
+    AuthenticateCommand, BotDifficulty, Command, GameStateSnapshot, JoinGameCommand, Message,
+    PlayerAction, PlayerRole, RevealCardCommand, SetPlayerRoleCommand, SetPlayerTeamCommand,
+    ShareCodenameCommand, Team, Turn,
+};
+use crate::server::on_player_command;
+use crate::universe::Universe;
+
+struct Bot {
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    difficulty: BotDifficulty,
+    /// The turn, clue count and guess count the bot last acted on.
+    ///
+    /// Snapshots are broadcast for every change in the game (chat, players
+    /// joining, ready marks) so the same position arrives more than once.
+    last_move: Option<(Turn, usize, usize)>,
+}
+
+impl Bot {
+    async fn send(&self, cmd: Command) {
+        if let Err(err) = on_player_command(self.universe.clone(), self.player_id, cmd).await {
+            log::debug!("bot {} command failed: {}", self.player_id, err.message());
+        }
+    }
+
+    async fn on_message(&mut self, msg: ws::Message) {
+        let message: Message = match msg.to_str().map(serde_json::from_str) {
+            Ok(Ok(message)) => message,
+            _ => return,
+        };
+        if let Message::GameStateSnapshot(snapshot) = message {
+            self.on_game_state(snapshot).await;
+        }
+    }
+
+    async fn on_game_state(&mut self, snapshot: GameStateSnapshot) {
+        let my_state = match snapshot
+            .players
+            .iter()
+            .find(|x| x.player.id == self.player_id)
+        {
+            Some(my_state) => my_state,
+            None => return,
+        };
+
+        if snapshot.turn == Turn::Pregame {
+            self.last_move = None;
+            // changing roles resets the ready flag.
+            if my_state.team.is_some() && !my_state.ready {
+                self.send(Command::MarkReady).await;
+            }
+            return;
+        }
+
+        let team = match my_state.team {
+            Some(team) => team,
+            None => return,
+        };
+        let action = match my_state.get_turn_player_action(snapshot.turn) {
+            Some(action) => action,
+            None => return,
+        };
+        let current_move = (
+            snapshot.turn,
+            snapshot.clues.len(),
+            snapshot.clue.as_ref().map_or(0, |x| x.guesses),
+        );
+        if self.last_move == Some(current_move) {
+            return;
+        }
+        self.last_move = Some(current_move);
+
+        match action {
+            PlayerAction::ShareCodename => {
+                let clue = spymaster::find_clue(
+                    Associations::get(),
+                    &snapshot.tiles,
+                    team,
+                    self.difficulty,
+                    &mut thread_rng(),
+                );
+                if let Some(clue) = clue {
+                    self.send(Command::ShareCodename(ShareCodenameCommand {
+                        codename: clue.word,
+                        number: clue.number,
+                        unlimited: false,
+                    }))
+                    .await;
+                }
+            }
+            PlayerAction::Guess => {
+                let clue = match snapshot.clue {
+                    Some(ref clue) if clue.team == team => clue,
+                    _ => return,
+                };
+                let guess = operative::pick_guess(
+                    Associations::get(),
+                    &snapshot.tiles,
+                    clue,
+                    self.difficulty,
+                    &mut thread_rng(),
+                );
+                self.send(match guess {
+                    operative::Guess::Reveal(index) => {
+                        Command::RevealCard(RevealCardCommand { index })
+                    }
+                    operative::Guess::EndTurn => Command::EndTurn,
+                })
+                .await;
+            }
+        }
+    }
+}
+
+/// Adds a bot to a game.
+///
+/// The bot joins the game with the given join code, takes a seat and keeps
+/// playing until it is removed from the universe.
+pub fn spawn_bot(
+    universe: Arc<Universe>,
+    join_code: String,
+    team: Team,
+    role: PlayerRole,
+    difficulty: BotDifficulty,
+) {
+    tokio::spawn(async move {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let player_id = universe.add_bot(tx).await;
+        let mut bot = Bot {
+            universe: universe.clone(),
+            player_id,
+            difficulty,
+            last_move: None,
+        };
+
+        log::info!("bot {} joining game {}", player_id, &join_code);
+        bot.send(Command::Authenticate(AuthenticateCommand {
+            nickname: match team {
+                Team::Red => "Red Bot".into(),
+                Team::Blue => "Blue Bot".into(),
+            },
+            session_token: None,
+            credentials: None,
+        }))
+        .await;
+        bot.send(Command::JoinGame(JoinGameCommand { join_code }))
+            .await;
+        if universe.get_player_game(player_id).await.is_none() {
+            universe.remove_player(player_id).await;
+            return;
+        }
+        bot.send(Command::SetPlayerTeam(SetPlayerTeamCommand {
+            team: Some(team),
+        }))
+        .await;
+        bot.send(Command::SetPlayerRole(SetPlayerRoleCommand { role }))
+            .await;
+
+        // the channel closes when the bot is removed from the universe.
+        while let Some(msg) = rx.recv().await {
+            if let Ok(msg) = msg {
+                bot.on_message(msg).await;
+            }
+        }
+        log::info!("bot {} stopped", player_id);
+    });
+}
@@ -0,0 +1,171 @@
+use std::cmp::Ordering;
+
+use rand::Rng;
+
+use crate::bot::associations::Associations;
+use crate::protocol::{BotDifficulty, Clue, Tile};
+
+/// What the operative bot wants to do next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Guess {
+    Reveal(usize),
+    EndTurn,
+}
+
+struct Strategy {
+    /// How strongly a word has to relate to the clue for the bot to keep
+    /// guessing after the first guess.
+    threshold: f32,
+    /// How much random noise is added to the scores.
+    noise: f32,
+}
+
+impl Strategy {
+    fn for_difficulty(difficulty: BotDifficulty) -> Strategy {
+        match difficulty {
+            BotDifficulty::Easy => Strategy {
+                threshold: 0.3,
+                noise: 0.3,
+            },
+            BotDifficulty::Normal => Strategy {
+                threshold: 0.45,
+                noise: 0.1,
+            },
+            BotDifficulty::Hard => Strategy {
+                threshold: 0.55,
+                noise: 0.01,
+            },
+        }
+    }
+}
+
+/// Picks the next guess for a clue.
+///
+/// The tiles are the operative's projection of the board.  The bot always
+/// makes at least one guess and never uses the bonus guess.
+pub fn pick_guess<R: Rng>(
+    associations: &Associations,
+    tiles: &[Tile],
+    clue: &Clue,
+    difficulty: BotDifficulty,
+    rng: &mut R,
+) -> Guess {
+    if clue.number > 0 && clue.guesses >= clue.number {
+        return Guess::EndTurn;
+    }
+
+    let strategy = Strategy::for_difficulty(difficulty);
+    let best = tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| !tile.spotted)
+        .map(|(idx, tile)| {
+            let score = associations.score(&clue.word, &tile.codeword);
+            (idx, score + rng.gen::<f32>() * strategy.noise)
+        })
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+    match best {
+        Some((idx, score)) if clue.guesses == 0 || score >= strategy.threshold => {
+            Guess::Reveal(idx)
+        }
+        _ => Guess::EndTurn,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::protocol::{Character, Team};
+
+    fn tiles(codewords: &[&str]) -> Vec<Tile> {
+        codewords
+            .iter()
+            .map(|codeword| Tile {
+                codeword: codeword.to_string(),
+                character: Character::Unknown,
+                spotted: false,
+            })
+            .collect()
+    }
+
+    fn clue(number: usize, guesses: usize) -> Clue {
+        Clue {
+            team: Team::Red,
+            word: "water".into(),
+            number,
+            unlimited: number == 0,
+            guesses,
+        }
+    }
+
+    fn associations() -> Associations {
+        Associations::parse("ocean: sea water wave\nriver: water bank\n")
+    }
+
+    #[test]
+    fn test_pick_guess() {
+        let associations = associations();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut tiles = tiles(&["Apple", "Ocean", "Knife", "River"]);
+
+        // the strongest association is guessed first.
+        let guess = pick_guess(
+            &associations,
+            &tiles,
+            &clue(2, 0),
+            BotDifficulty::Hard,
+            &mut rng,
+        );
+        assert_eq!(guess, Guess::Reveal(3));
+        tiles[3].spotted = true;
+        let guess = pick_guess(
+            &associations,
+            &tiles,
+            &clue(2, 1),
+            BotDifficulty::Hard,
+            &mut rng,
+        );
+        assert_eq!(guess, Guess::Reveal(1));
+        tiles[1].spotted = true;
+
+        // the bonus guess is never used.
+        let guess = pick_guess(
+            &associations,
+            &tiles,
+            &clue(2, 2),
+            BotDifficulty::Hard,
+            &mut rng,
+        );
+        assert_eq!(guess, Guess::EndTurn);
+    }
+
+    #[test]
+    fn test_pick_guess_stops_without_match() {
+        let associations = associations();
+        let mut rng = StdRng::seed_from_u64(0);
+        let tiles = tiles(&["Apple", "Knife"]);
+
+        // the first guess is made even if nothing matches.
+        let guess = pick_guess(
+            &associations,
+            &tiles,
+            &clue(0, 0),
+            BotDifficulty::Hard,
+            &mut rng,
+        );
+        assert!(matches!(guess, Guess::Reveal(_)));
+        let guess = pick_guess(
+            &associations,
+            &tiles,
+            &clue(0, 1),
+            BotDifficulty::Hard,
+            &mut rng,
+        );
+        assert_eq!(guess, Guess::EndTurn);
+    }
+}
@@ -0,0 +1,237 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use rand::Rng;
+
+use crate::bot::associations::Associations;
+use crate::protocol::{BotDifficulty, Character, Team, Tile};
+
+/// A clue picked by the spymaster bot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clue {
+    pub word: String,
+    pub number: usize,
+}
+
+struct Strategy {
+    /// The largest number the bot will give with a clue.
+    max_number: usize,
+    /// How much more a clue has to relate to an own agent than to any
+    /// other word for the agent to be counted.
+    margin: f32,
+    /// How many of the best clues the bot randomly picks from.
+    choices: usize,
+}
+
+impl Strategy {
+    fn for_difficulty(difficulty: BotDifficulty) -> Strategy {
+        match difficulty {
+            BotDifficulty::Easy => Strategy {
+                max_number: 1,
+                margin: 0.25,
+                choices: 5,
+            },
+            BotDifficulty::Normal => Strategy {
+                max_number: 2,
+                margin: 0.15,
+                choices: 3,
+            },
+            BotDifficulty::Hard => Strategy {
+                max_number: 4,
+                margin: 0.05,
+                choices: 1,
+            },
+        }
+    }
+}
+
+/// Checks if a clue is allowed for the given board.
+///
+/// Clues cannot be a word on the board or be part of one.
+fn is_legal_clue(clue: &str, tiles: &[Tile]) -> bool {
+    tiles.iter().filter(|tile| !tile.spotted).all(|tile| {
+        let codeword = tile.codeword.to_lowercase();
+        !codeword.contains(clue) && !clue.contains(&codeword)
+    })
+}
+
+/// How bad it is if operatives pick a tile of this character.
+fn danger_weight(character: Character) -> f32 {
+    match character {
+        Character::Assassin => 1.5,
+        Character::Bystander => 0.7,
+        _ => 1.0,
+    }
+}
+
+/// Finds a clue for the agents of a team.
+///
+/// The tiles need to be the spymaster's projection of the board with all
+/// characters revealed.
+pub fn find_clue<R: Rng>(
+    associations: &Associations,
+    tiles: &[Tile],
+    team: Team,
+    difficulty: BotDifficulty,
+    rng: &mut R,
+) -> Option<Clue> {
+    let strategy = Strategy::for_difficulty(difficulty);
+    let own_character = match team {
+        Team::Red => Character::RedAgent,
+        Team::Blue => Character::BlueAgent,
+    };
+    let unspotted: Vec<&Tile> = tiles.iter().filter(|tile| !tile.spotted).collect();
+    let own: Vec<&str> = unspotted
+        .iter()
+        .filter(|tile| tile.character == own_character)
+        .map(|tile| tile.codeword.as_str())
+        .collect();
+    if own.is_empty() {
+        return None;
+    }
+
+    let candidates: BTreeSet<&str> = own
+        .iter()
+        .flat_map(|word| associations.associated_words(word))
+        .map(|x| x.as_str())
+        .filter(|x| is_legal_clue(x, tiles))
+        .collect();
+
+    let mut scored = vec![];
+    for candidate in candidates {
+        // operatives are likely to confuse the clue with the most
+        // strongly related word that is not one of our agents.
+        let danger = unspotted
+            .iter()
+            .filter(|tile| tile.character != own_character)
+            .map(|tile| {
+                associations.score(candidate, &tile.codeword) * danger_weight(tile.character)
+            })
+            .fold(0.0, f32::max);
+
+        let mut strengths: Vec<f32> = own
+            .iter()
+            .map(|word| associations.score(candidate, word))
+            .filter(|&strength| strength > danger + strategy.margin)
+            .collect();
+        if strengths.is_empty() {
+            continue;
+        }
+        strengths.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        strengths.truncate(strategy.max_number);
+
+        let score = strengths.iter().sum::<f32>() - danger;
+        scored.push((
+            score,
+            Clue {
+                word: candidate.to_string(),
+                number: strengths.len(),
+            },
+        ));
+    }
+
+    if scored.is_empty() {
+        // nothing is safe, fall back to the first usable association.
+        return own
+            .iter()
+            .flat_map(|word| associations.associated_words(word))
+            .find(|x| is_legal_clue(x, tiles))
+            .map(|word| Clue {
+                word: word.to_string(),
+                number: 1,
+            });
+    }
+
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    scored.truncate(strategy.choices);
+    let idx = rng.gen_range(0, scored.len());
+    Some(scored.swap_remove(idx).1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn tile(codeword: &str, character: Character) -> Tile {
+        Tile {
+            codeword: codeword.into(),
+            character,
+            spotted: false,
+        }
+    }
+
+    fn associations() -> Associations {
+        Associations::parse(
+            "ocean: sea water wave\n\
+             river: water bank\n\
+             bank: money river\n\
+             shark: sea ocean\n",
+        )
+    }
+
+    #[test]
+    fn test_find_clue() {
+        let tiles = vec![
+            tile("Ocean", Character::RedAgent),
+            tile("River", Character::RedAgent),
+            tile("Bank", Character::BlueAgent),
+            tile("Apple", Character::Bystander),
+        ];
+        let clue = find_clue(
+            &associations(),
+            &tiles,
+            Team::Red,
+            BotDifficulty::Hard,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(
+            clue,
+            Some(Clue {
+                word: "water".into(),
+                number: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_clue_avoids_assassin() {
+        let tiles = vec![
+            tile("Ocean", Character::RedAgent),
+            tile("River", Character::RedAgent),
+            tile("Shark", Character::Assassin),
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let clue = find_clue(
+                &associations(),
+                &tiles,
+                Team::Red,
+                BotDifficulty::Easy,
+                &mut rng,
+            )
+            .unwrap();
+            assert_ne!(clue.word, "sea");
+            assert_eq!(clue.number, 1);
+        }
+    }
+
+    #[test]
+    fn test_find_clue_without_agents() {
+        let mut tiles = vec![
+            tile("Ocean", Character::RedAgent),
+            tile("River", Character::BlueAgent),
+        ];
+        tiles[0].spotted = true;
+        let clue = find_clue(
+            &associations(),
+            &tiles,
+            Team::Red,
+            BotDifficulty::Normal,
+            &mut StdRng::seed_from_u64(0),
+        );
+        assert_eq!(clue, None);
+    }
+}
@@ -0,0 +1,164 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// The server configuration.
+///
+/// The config is loaded from a TOML file.  All keys are optional and fall
+/// back to sensible defaults.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Config {
+    pub chat: ChatConfig,
+    pub moderation: ModerationConfig,
+    pub anti_cheat: AntiCheatConfig,
+    pub admin: AdminConfig,
+    pub timeouts: TimeoutsConfig,
+    pub shutdown: ShutdownConfig,
+    pub accounts: AccountsConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ChatConfig {
+    /// The maximum length of a chat message in characters.
+    pub max_message_length: usize,
+    /// How many messages a player can send in a burst.
+    pub rate_limit_burst: u32,
+    /// How many messages per second a player can send on average.
+    pub rate_limit_per_second: f64,
+}
+
+impl Default for ChatConfig {
+    fn default() -> ChatConfig {
+        ChatConfig {
+            max_message_length: 300,
+            rate_limit_burst: 5,
+            rate_limit_per_second: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// Regular expressions for text that is not permitted in chat
+    /// messages and nicknames.  Matching is case insensitive.
+    pub blocklist: Vec<String>,
+    /// Optional path to a file with one additional pattern per line.
+    pub blocklist_file: Option<PathBuf>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AntiCheatConfig {
+    /// Treat spectators connecting from the same address as a seated player
+    /// as suspicious.  This is off by default as players behind the same
+    /// NAT would all share an address.
+    pub match_remote_addr: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TimeoutsConfig {
+    /// Seconds without player activity after which a game is closed.
+    pub game_idle_secs: u64,
+    /// Seconds without player activity after which a finished game is
+    /// closed.
+    pub finished_game_idle_secs: u64,
+    /// Seconds between websocket pings and checks for idle games.
+    pub heartbeat_interval_secs: u64,
+    /// Seconds without any frame from a client after which it is
+    /// disconnected.
+    pub heartbeat_timeout_secs: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> TimeoutsConfig {
+        TimeoutsConfig {
+            game_idle_secs: 3600,
+            finished_game_idle_secs: 600,
+            heartbeat_interval_secs: 30,
+            heartbeat_timeout_secs: 90,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ShutdownConfig {
+    /// The reason shown to players when the server shuts down.
+    pub reason: String,
+    /// Seconds after which clients are told to reconnect.  If not set
+    /// clients are not given a hint.
+    pub reconnect_after_secs: Option<u64>,
+    /// Seconds between telling players about the shutdown and closing
+    /// their connections.
+    pub grace_period_secs: u64,
+    /// The directory the state of running games is written to on
+    /// shutdown.  Games are discarded if this is not set.
+    pub persist_dir: Option<PathBuf>,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> ShutdownConfig {
+        ShutdownConfig {
+            reason: "The server is restarting.".into(),
+            reconnect_after_secs: None,
+            grace_period_secs: 5,
+            persist_dir: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AccountsConfig {
+    /// Path to the SQLite database with the accounts.  Accounts are
+    /// disabled if this is not set.
+    pub database: Option<PathBuf>,
+    /// The directory sign-in tokens are written to instead of sending
+    /// mails.
+    pub mail_dir: PathBuf,
+    /// Seconds after which a sign-in token expires.
+    pub magic_token_ttl_secs: u64,
+}
+
+impl Default for AccountsConfig {
+    fn default() -> AccountsConfig {
+        AccountsConfig {
+            database: None,
+            mail_dir: PathBuf::from("mail"),
+            magic_token_ttl_secs: 900,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// The bearer token required for the `/admin` API.  The API is
+    /// disabled if no token is configured.
+    pub token: Option<String>,
+}
+
+impl Config {
+    /// Loads the config from the file `WEBGAME_CONFIG` points to.
+    ///
+    /// If the variable is not set the default config is returned.
+    pub fn from_env() -> Result<Config, io::Error> {
+        match env::var_os("WEBGAME_CONFIG") {
+            Some(path) => Config::from_path(Path::new(&path)),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Loads the config from a TOML file.
+    pub fn from_path(path: &Path) -> Result<Config, io::Error> {
+        let contents = fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
@@ -1,39 +1,298 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
 use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 
+use serde::Serialize;
 use uuid::Uuid;
 
 use crate::board::Board;
+use crate::metrics::Metrics;
+use crate::projection::can_see_key;
 use crate::protocol::{
-    GameInfo, GamePlayerState, GameStateSnapshot, Message, PlayerDisconnectedMessage, PlayerRole,
-    Team, Turn,
+    Character, ChatChannel, ChatMessage, Clue, ClueGuess, ClueLogEntry, ErrorCode, GameInfo,
+    GameListEntry, GameMode, GamePlayerState, GameStateSnapshot, Message, PlayerAction,
+    PlayerDisconnectedMessage, PlayerRole, PlayerStats, ProtocolError, SpectatorVisibility, Team,
+    Turn,
 };
-use crate::universe::Universe;
+use crate::universe::{PlayerOrigin, Universe};
+
+/// The number of chat messages kept for players joining later.
+const CHAT_HISTORY_SIZE: usize = 100;
+
+/// The maximum number of characters in a nickname.
+pub const MAX_NICKNAME_LENGTH: usize = 16;
+
+/// The state of a game as written to disk on shutdown.
+#[derive(Serialize)]
+struct PersistedGame<'a> {
+    game_id: Uuid,
+    join_code: &'a str,
+    public: bool,
+    mode: GameMode,
+    state: GameStateSnapshot,
+}
+
+struct ChatLogEntry {
+    sender_team: Option<Team>,
+    message: ChatMessage,
+}
 
 pub struct GameState {
     players: BTreeMap<Uuid, GamePlayerState>,
+    host_id: Option<Uuid>,
     turn: Turn,
     board: Board,
+    clue: Option<Clue>,
+    winner: Option<Team>,
+    /// The spymaster who gave the current clue.
+    clue_giver: Option<Uuid>,
+    /// The agents of the guessing team found for the current clue.
+    clue_hits: usize,
+    /// The statistics of the running game, recorded once it ends.
+    stats: HashMap<Uuid, PlayerStats>,
+    clue_log: Vec<ClueLogEntry>,
+    chat_log: VecDeque<ChatLogEntry>,
+    last_activity: Instant,
+    metrics: Arc<Metrics>,
+}
+
+impl GameState {
+    fn set_turn(&mut self, turn: Turn) {
+        self.turn = turn;
+        self.metrics.turn_changed(turn);
+    }
+
+    /// Credits the current clue to the spymaster who gave it.
+    fn finish_clue(&mut self) {
+        let clue = match self.clue.take() {
+            Some(clue) => clue,
+            None => return,
+        };
+        if let Some(giver) = self.clue_giver.take() {
+            let stats = self.stats.entry(giver).or_default();
+            stats.clues_given += 1;
+            stats.clue_guesses += clue.guesses as u32;
+            if self.clue_hits >= clue.number.max(1) {
+                stats.successful_clues += 1;
+            }
+        }
+    }
+
+    /// Hands the turn to the spymaster of the other team.
+    fn end_turn(&mut self, team: Team) {
+        self.finish_clue();
+        self.set_turn(match team.other() {
+            Team::Red => Turn::RedSpymasterThinking,
+            Team::Blue => Turn::BlueSpymasterThinking,
+        });
+    }
+
+    fn end_game(&mut self, winner: Team) {
+        self.finish_clue();
+        self.winner = Some(winner);
+        self.set_turn(Turn::Endgame);
+
+        for player_state in self.players.values() {
+            let team = match player_state.team {
+                Some(team) if player_state.role != PlayerRole::Spectator => team,
+                _ => continue,
+            };
+            let won = (team == winner) as u32;
+            let stats = self.stats.entry(player_state.player.id).or_default();
+            if player_state.role == PlayerRole::Spymaster {
+                stats.games_played_spymaster += 1;
+                stats.games_won_spymaster += won;
+            } else {
+                stats.games_played_operative += 1;
+                stats.games_won_operative += won;
+            }
+        }
+    }
+
+    /// Takes the statistics of the players with an account.
+    fn take_account_stats(&mut self) -> Vec<(String, PlayerStats)> {
+        let players = &self.players;
+        self.stats
+            .drain()
+            .filter_map(|(player_id, stats)| {
+                let account = players.get(&player_id)?.player.account.clone()?;
+                Some((account, stats))
+            })
+            .collect()
+    }
+
+    /// Returns the team of a player if it's their turn to guess.
+    fn guessing_team(&self, player_id: Uuid) -> Result<Team, ProtocolError> {
+        let player_state = match self.players.get(&player_id) {
+            Some(player_state) => player_state,
+            None => return Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game")),
+        };
+        match player_state.team {
+            Some(team)
+                if player_state.get_turn_player_action(self.turn) == Some(PlayerAction::Guess) =>
+            {
+                Ok(team)
+            }
+            _ => Err(ProtocolError::new(
+                ErrorCode::NotYourTurn,
+                "it's not your turn to guess",
+            )),
+        }
+    }
+}
+
+/// Returns the spectators sharing a session or address with a seated player.
+fn find_suspect_spectators(
+    players: &BTreeMap<Uuid, GamePlayerState>,
+    origins: &HashMap<Uuid, PlayerOrigin>,
+    match_remote_addr: bool,
+) -> HashSet<Uuid> {
+    let seated: Vec<&PlayerOrigin> = players
+        .values()
+        .filter(|x| x.team.is_some() && x.role != PlayerRole::Spectator)
+        .filter_map(|x| origins.get(&x.player.id))
+        .collect();
+
+    players
+        .values()
+        .filter(|x| x.role == PlayerRole::Spectator)
+        .filter(|x| {
+            let origin = match origins.get(&x.player.id) {
+                Some(origin) => origin,
+                None => return false,
+            };
+            seated.iter().any(|other| {
+                (origin.session_token.is_some() && origin.session_token == other.session_token)
+                    || (match_remote_addr
+                        && origin.remote_addr.is_some()
+                        && origin.remote_addr == other.remote_addr)
+            })
+        })
+        .map(|x| x.player.id)
+        .collect()
+}
+
+/// Checks if a player can read a message sent on a channel.
+fn can_read_chat(
+    reader: &GamePlayerState,
+    channel: ChatChannel,
+    sender_team: Option<Team>,
+) -> bool {
+    match channel {
+        ChatChannel::All => true,
+        ChatChannel::Team => reader.team.is_some() && reader.team == sender_team,
+        ChatChannel::Spectators => reader.role == PlayerRole::Spectator,
+    }
+}
+
+/// Checks if a player may write to a chat channel.
+fn check_chat_channel(
+    sender: &GamePlayerState,
+    channel: ChatChannel,
+    turn: Turn,
+) -> Result<(), ProtocolError> {
+    match channel {
+        ChatChannel::All => {}
+        ChatChannel::Team => {
+            if sender.team.is_none() {
+                return Err(ProtocolError::new(ErrorCode::NotInTeam, "not in a team"));
+            }
+            // spymasters are not allowed to talk to their operatives
+            // once the game is running as they could leak hints.
+            if sender.role == PlayerRole::Spymaster
+                && turn != Turn::Pregame
+                && turn != Turn::Endgame
+            {
+                return Err(ProtocolError::new(
+                    ErrorCode::ChannelNotAllowed,
+                    "spymasters cannot use the team channel during play",
+                )
+                .with_field("channel"));
+            }
+        }
+        ChatChannel::Spectators => {
+            if sender.role != PlayerRole::Spectator {
+                return Err(ProtocolError::new(
+                    ErrorCode::ChannelNotAllowed,
+                    "only spectators can use the spectator channel",
+                )
+                .with_field("channel"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns a nickname that is not yet used by another player in the game.
+fn unique_nickname(players: &BTreeMap<Uuid, GamePlayerState>, nickname: &str) -> String {
+    let is_taken = |nickname: &str| {
+        let nickname = nickname.to_lowercase();
+        players
+            .values()
+            .any(|x| x.player.nickname.to_lowercase() == nickname)
+    };
+    if !is_taken(nickname) {
+        return nickname.to_string();
+    }
+    (2..)
+        .map(|n| {
+            // shorten the nickname so that the suffix still fits
+            let suffix = format!(" ({})", n);
+            let nickname: String = nickname
+                .chars()
+                .take(MAX_NICKNAME_LENGTH.saturating_sub(suffix.len()))
+                .collect();
+            format!("{}{}", nickname.trim_end(), suffix)
+        })
+        .find(|x| !is_taken(x))
+        .unwrap()
 }
 
 pub struct Game {
     id: Uuid,
     join_code: String,
+    public: bool,
+    mode: GameMode,
+    spectator_visibility: SpectatorVisibility,
+    created_at: Instant,
     universe: Weak<Universe>,
     game_state: Arc<Mutex<GameState>>,
 }
 
 impl Game {
-    pub fn new(join_code: String, universe: Arc<Universe>) -> Game {
+    pub fn new(
+        join_code: String,
+        public: bool,
+        mode: GameMode,
+        spectator_visibility: SpectatorVisibility,
+        universe: Arc<Universe>,
+    ) -> Game {
         Game {
             id: Uuid::new_v4(),
             join_code,
+            public,
+            mode,
+            spectator_visibility,
+            created_at: Instant::now(),
             universe: Arc::downgrade(&universe),
             game_state: Arc::new(Mutex::new(GameState {
                 players: BTreeMap::new(),
+                host_id: None,
                 turn: Turn::Pregame,
                 board: Board::new(),
+                clue: None,
+                winner: None,
+                clue_giver: None,
+                clue_hits: 0,
+                stats: HashMap::new(),
+                clue_log: vec![],
+                chat_log: VecDeque::new(),
+                last_activity: Instant::now(),
+                metrics: universe.metrics().clone(),
             })),
         }
     }
@@ -53,7 +312,53 @@ impl Game {
         }
     }
 
-    pub async fn is_joinable(&self) -> bool {
+    /// Returns `true` if the game shows up in the lobby listing.
+    pub fn is_public(&self) -> bool {
+        self.public
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    /// Returns the entry for this game in the lobby listing.
+    pub async fn list_entry(&self) -> GameListEntry {
+        let game_state = self.game_state.lock().await;
+        GameListEntry {
+            join_code: self.join_code.clone(),
+            host_nickname: game_state
+                .host_id
+                .and_then(|host_id| game_state.players.get(&host_id))
+                .map(|x| x.player.nickname.clone())
+                .unwrap_or_default(),
+            player_count: game_state.players.len(),
+            mode: self.mode,
+            is_pregame: game_state.turn == Turn::Pregame,
+        }
+    }
+
+    /// Returns how long ago the game was created.
+    pub fn age(&self) -> Duration {
+        self.created_at.elapsed()
+    }
+
+    /// Records that a player did something in the game.
+    pub async fn touch(&self) {
+        self.game_state.lock().await.last_activity = Instant::now();
+    }
+
+    /// Returns how long nobody did anything in the game.
+    pub async fn idle_time(&self) -> Duration {
+        self.game_state.lock().await.last_activity.elapsed()
+    }
+
+    /// Returns the current turn.
+    pub async fn turn(&self) -> Turn {
+        self.game_state.lock().await.turn
+    }
+
+    /// Returns `true` if players can still pick their team and role.
+    pub async fn is_pregame(&self) -> bool {
         self.game_state.lock().await.turn == Turn::Pregame
     }
 
@@ -76,10 +381,11 @@ impl Game {
         }
 
         // TODO: `set_player_game_id` also looks up.
-        let player_info = match universe.get_player_info(player_id).await {
+        let mut player_info = match universe.get_player_info(player_id).await {
             Some(player_info) => player_info,
             None => return,
         };
+        player_info.nickname = unique_nickname(&game_state.players, &player_info.nickname);
 
         let state = GamePlayerState {
             player: player_info,
@@ -88,25 +394,51 @@ impl Game {
             ready: false,
         };
         game_state.players.insert(state.player.id, state.clone());
+        if game_state.host_id.is_none() && !state.player.is_bot {
+            game_state.host_id = Some(player_id);
+        }
 
         drop(game_state);
         self.broadcast(&Message::PlayerConnected(state)).await;
     }
 
     pub async fn remove_player(&self, player_id: Uuid) {
-        self.universe().set_player_game_id(player_id, None).await;
+        let universe = self.universe();
+        universe.set_player_game_id(player_id, None).await;
 
         let mut game_state = self.game_state.lock().await;
         if game_state.players.remove(&player_id).is_some() {
+            if game_state.host_id == Some(player_id) {
+                game_state.host_id = game_state
+                    .players
+                    .values()
+                    .find(|x| !x.player.is_bot)
+                    .map(|x| x.player.id);
+            }
+
+            // bots do not stick around once all humans left.
+            let bots: Vec<Uuid> = if game_state.players.values().all(|x| x.player.is_bot) {
+                game_state.players.keys().copied().collect()
+            } else {
+                vec![]
+            };
+            for bot_id in bots.iter() {
+                game_state.players.remove(bot_id);
+            }
+
             drop(game_state);
             self.broadcast(&Message::PlayerDisconnected(PlayerDisconnectedMessage {
                 player_id,
             }))
             .await;
+
+            for bot_id in bots {
+                universe.remove_player(bot_id).await;
+            }
         }
 
         if self.is_empty().await {
-            self.universe().remove_game(self.id()).await;
+            universe.remove_game(self.id()).await;
         }
     }
 
@@ -158,24 +490,204 @@ impl Game {
 
     pub async fn mark_player_ready(&self, player_id: Uuid) {
         let mut game_state = self.game_state.lock().await;
+        let mut marked_by_bot = false;
         if let Some(player_state) = game_state.players.get_mut(&player_id) {
             player_state.ready = true;
+            marked_by_bot = player_state.player.is_bot;
         }
 
         let mut flags = HashSet::new();
+        let mut all_ready = true;
 
         for player in game_state.players.values() {
             match (player.team, player.role) {
                 (None, _) | (_, PlayerRole::Spectator) => {}
                 (Some(team), role) => {
                     flags.insert((team, role));
+                    all_ready = all_ready && player.ready;
                 }
             }
         }
 
-        if flags.len() == 4 {
-            game_state.turn = game_state.board.initial_turn();
+        // bots mark themselves ready as soon as they are seated, so they
+        // only start the game once every seated player is ready.
+        if flags.len() == 4 && (all_ready || !marked_by_bot) {
+            let turn = game_state.board.initial_turn();
+            game_state.set_turn(turn);
+        }
+    }
+
+    /// Shares a codename with the operatives of the spymaster's team.
+    ///
+    /// Without a number the operatives can guess as often as they like, just
+    /// like for a clue of zero.
+    pub async fn share_codename(
+        &self,
+        player_id: Uuid,
+        codename: String,
+        number: Option<usize>,
+    ) -> Result<(), ProtocolError> {
+        let mut game_state = self.game_state.lock().await;
+        let player_state = match game_state.players.get(&player_id) {
+            Some(player_state) => player_state,
+            None => return Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game")),
+        };
+        let team = match player_state.team {
+            Some(team)
+                if player_state.get_turn_player_action(game_state.turn)
+                    == Some(PlayerAction::ShareCodename) =>
+            {
+                team
+            }
+            _ => {
+                return Err(ProtocolError::new(
+                    ErrorCode::NotYourTurn,
+                    "it's not your turn to share a codename",
+                ))
+            }
+        };
+
+        let codename = codename.trim().to_lowercase();
+        if codename.is_empty() || codename.contains(char::is_whitespace) {
+            return Err(ProtocolError::new(
+                ErrorCode::CodenameNotSingleWord,
+                "the codename must be a single word",
+            )
+            .with_field("codename"));
+        }
+        if game_state.board.is_unspotted_codeword(&codename) {
+            return Err(ProtocolError::new(
+                ErrorCode::CodenameOnBoard,
+                "the codename cannot be a word on the board",
+            )
+            .with_field("codename"));
+        }
+
+        let unlimited = number.is_none();
+        let number = number.unwrap_or(0);
+        game_state.set_turn(match team {
+            Team::Red => Turn::RedOperativesGuessing,
+            Team::Blue => Turn::BlueOperativesGuessing,
+        });
+        game_state.clue = Some(Clue {
+            team,
+            word: codename.clone(),
+            number,
+            unlimited,
+            guesses: 0,
+        });
+        game_state.clue_giver = Some(player_id);
+        game_state.clue_hits = 0;
+        game_state.clue_log.push(ClueLogEntry {
+            player_id,
+            team,
+            word: codename.clone(),
+            number,
+            unlimited,
+            guesses: vec![],
+        });
+        drop(game_state);
+
+        let text = if unlimited {
+            format!("codename: {} (unlimited)", codename)
+        } else {
+            format!("codename: {} {}", codename, number)
+        };
+        self.send_chat(player_id, text, ChatChannel::All).await
+    }
+
+    /// Reveals a card for the operatives of the player's team.
+    pub async fn reveal_card(&self, player_id: Uuid, index: usize) -> Result<(), ProtocolError> {
+        let mut game_state = self.game_state.lock().await;
+        let team = game_state.guessing_team(player_id)?;
+        let character = match game_state.board.spot(index) {
+            Some(character) => character,
+            None => {
+                return Err(ProtocolError::new(
+                    ErrorCode::CardNotRevealable,
+                    "this card cannot be revealed",
+                )
+                .with_field("index"))
+            }
+        };
+
+        let more_guesses = match game_state.clue {
+            Some(ref mut clue) => {
+                clue.guesses += 1;
+                clue.allows_more_guesses()
+            }
+            None => false,
+        };
+        if let Some(entry) = game_state.clue_log.last_mut() {
+            entry.guesses.push(ClueGuess {
+                player_id,
+                index,
+                character,
+            });
+        }
+        if character == team.agent() {
+            game_state.clue_hits += 1;
+        } else if character == Character::Assassin {
+            game_state.stats.entry(player_id).or_default().assassin_hits += 1;
+        }
+
+        if character == Character::Assassin {
+            game_state.end_game(team.other());
+        } else if game_state.board.agents_left(team) == 0 {
+            game_state.end_game(team);
+        } else if game_state.board.agents_left(team.other()) == 0 {
+            game_state.end_game(team.other());
+        } else if character != team.agent() || !more_guesses {
+            game_state.end_turn(team);
+        }
+
+        if game_state.turn == Turn::Endgame {
+            let records = game_state.take_account_stats();
+            drop(game_state);
+            self.universe().record_stats(records).await;
+        }
+        Ok(())
+    }
+
+    /// Ends the guessing of the player's team.
+    pub async fn end_turn(&self, player_id: Uuid) -> Result<(), ProtocolError> {
+        let mut game_state = self.game_state.lock().await;
+        let team = game_state.guessing_team(player_id)?;
+        if game_state.clue.as_ref().map_or(0, |x| x.guesses) == 0 {
+            return Err(ProtocolError::new(
+                ErrorCode::GuessRequired,
+                "operatives have to make at least one guess",
+            ));
+        }
+        game_state.end_turn(team);
+        Ok(())
+    }
+
+    /// Deals a new board for the same teams once the game is over.
+    pub async fn rematch(&self, player_id: Uuid) -> Result<(), ProtocolError> {
+        let mut game_state = self.game_state.lock().await;
+        if !game_state.players.contains_key(&player_id) {
+            return Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"));
+        }
+        if game_state.turn != Turn::Endgame {
+            return Err(ProtocolError::new(
+                ErrorCode::GameNotOver,
+                "the game is not over yet",
+            ));
         }
+
+        game_state.board = Board::new();
+        game_state.clue = None;
+        game_state.winner = None;
+        game_state.clue_giver = None;
+        game_state.clue_hits = 0;
+        game_state.stats.clear();
+        game_state.clue_log.clear();
+        for player_state in game_state.players.values_mut() {
+            player_state.ready = false;
+        }
+        game_state.set_turn(Turn::Pregame);
+        Ok(())
     }
 
     pub async fn broadcast(&self, message: &Message) {
@@ -186,30 +698,171 @@ impl Game {
         }
     }
 
+    /// Checks if a player may currently write to a chat channel.
+    pub async fn can_send_chat(
+        &self,
+        player_id: Uuid,
+        channel: ChatChannel,
+    ) -> Result<(), ProtocolError> {
+        let game_state = self.game_state.lock().await;
+        match game_state.players.get(&player_id) {
+            Some(sender) => check_chat_channel(sender, channel, game_state.turn),
+            None => Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game")),
+        }
+    }
+
+    /// Sends a chat message to all players listening on the given channel.
+    ///
+    /// Use `can_send_chat` to check if the player may use the channel.
+    pub async fn send_chat(
+        &self,
+        player_id: Uuid,
+        text: String,
+        channel: ChatChannel,
+    ) -> Result<(), ProtocolError> {
+        let mut game_state = self.game_state.lock().await;
+        let sender = match game_state.players.get(&player_id) {
+            Some(sender) => sender,
+            None => return Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game")),
+        };
+
+        let sender_team = sender.team;
+        let recipients: Vec<Uuid> = game_state
+            .players
+            .values()
+            .filter(|x| can_read_chat(x, channel, sender_team))
+            .map(|x| x.player.id)
+            .collect();
+        let message = ChatMessage {
+            player_id,
+            text,
+            channel,
+        };
+        game_state.chat_log.push_back(ChatLogEntry {
+            sender_team,
+            message: message.clone(),
+        });
+        while game_state.chat_log.len() > CHAT_HISTORY_SIZE {
+            game_state.chat_log.pop_front();
+        }
+        drop(game_state);
+
+        let universe = self.universe();
+        let message = Message::Chat(message);
+        for player_id in recipients {
+            universe.send(player_id, &message).await;
+        }
+        Ok(())
+    }
+
+    /// Sends the chat messages a player is allowed to read to them.
+    ///
+    /// This is used to catch up players joining a game late.
+    pub async fn send_chat_history(&self, player_id: Uuid) {
+        let universe = self.universe();
+        let game_state = self.game_state.lock().await;
+        let reader = match game_state.players.get(&player_id) {
+            Some(reader) => reader,
+            None => return,
+        };
+        for entry in game_state.chat_log.iter() {
+            if can_read_chat(reader, entry.message.channel, entry.sender_team) {
+                universe
+                    .send(player_id, &Message::Chat(entry.message.clone()))
+                    .await;
+            }
+        }
+    }
+
+    /// Returns the game state including the full key card.
+    ///
+    /// This is meant for operators and never sent to players.
+    pub async fn full_snapshot(&self) -> GameStateSnapshot {
+        let game_state = self.game_state.lock().await;
+        GameStateSnapshot {
+            players: game_state.players.values().cloned().collect(),
+            tiles: game_state.board.tiles(true),
+            turn: game_state.turn,
+            clue: game_state.clue.clone(),
+            winner: game_state.winner,
+            clues: game_state.clue_log.clone(),
+        }
+    }
+
+    /// Writes the full state of the game to `<join_code>.json` in `dir`.
+    pub async fn persist(&self, dir: &Path) -> Result<(), io::Error> {
+        let game = PersistedGame {
+            game_id: self.id,
+            join_code: self.join_code(),
+            public: self.is_public(),
+            mode: self.mode(),
+            state: self.full_snapshot().await,
+        };
+        let data = serde_json::to_vec_pretty(&game)?;
+        fs::write(dir.join(format!("{}.json", game.join_code)), data)
+    }
+
+    /// Removes all players and the game itself from the universe.
+    pub async fn close(&self) {
+        let universe = self.universe();
+        let players: Vec<GamePlayerState> = {
+            let mut game_state = self.game_state.lock().await;
+            let players = game_state.players.values().cloned().collect();
+            game_state.players.clear();
+            game_state.host_id = None;
+            players
+        };
+        for player_state in players {
+            let player_id = player_state.player.id;
+            if player_state.player.is_bot {
+                universe.remove_player(player_id).await;
+            } else {
+                universe.set_player_game_id(player_id, None).await;
+                universe.send(player_id, &Message::GameLeft).await;
+            }
+        }
+        universe.remove_game(self.id).await;
+    }
+
     pub async fn broadcast_state(&self) {
         let universe = self.universe();
         let game_state = self.game_state.lock().await;
-        for player_id in game_state.players.keys().copied() {
+        let origins = universe
+            .get_player_origins(game_state.players.keys().copied())
+            .await;
+        let suspects = find_suspect_spectators(
+            &game_state.players,
+            &origins,
+            universe.config().anti_cheat.match_remote_addr,
+        );
+        for player_id in suspects.iter() {
+            log::warn!(
+                "spectator {} in game {} shares an origin with a seated player",
+                player_id,
+                self.id
+            );
+        }
+
+        let players: Vec<GamePlayerState> = game_state.players.values().cloned().collect();
+        for player_state in game_state.players.values() {
+            let player_id = player_state.player.id;
             log::debug!("broadcast game state to {}", player_id);
-            let mut players = vec![];
-            let mut reveal = false;
-            for (&other_player_id, player_state) in game_state.players.iter() {
-                if player_id == other_player_id
-                    && (player_state.role == PlayerRole::Spymaster
-                        || player_state.role == PlayerRole::Spectator)
-                    && game_state.turn != Turn::Pregame
-                {
-                    reveal = true;
-                }
-                players.push(player_state.clone());
-            }
+            let reveal = can_see_key(
+                player_state,
+                game_state.turn,
+                self.spectator_visibility,
+                suspects.contains(&player_id),
+            );
             universe
                 .send(
                     player_id,
                     &Message::GameStateSnapshot(GameStateSnapshot {
-                        players,
+                        players: players.clone(),
                         tiles: game_state.board.tiles(reveal),
                         turn: game_state.turn,
+                        clue: game_state.clue.clone(),
+                        winner: game_state.winner,
+                        clues: game_state.clue_log.clone(),
                     }),
                 )
                 .await;
@@ -220,3 +873,61 @@ impl Game {
         self.game_state.lock().await.players.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::PlayerInfo;
+
+    fn players(nicknames: &[&str]) -> BTreeMap<Uuid, GamePlayerState> {
+        nicknames
+            .iter()
+            .map(|&nickname| {
+                let state = GamePlayerState {
+                    player: PlayerInfo {
+                        id: Uuid::new_v4(),
+                        nickname: nickname.into(),
+                        is_bot: false,
+                        account: None,
+                    },
+                    team: None,
+                    role: PlayerRole::Spectator,
+                    ready: false,
+                };
+                (state.player.id, state)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unique_nickname() {
+        assert_eq!(unique_nickname(&players(&[]), "Alice"), "Alice");
+        assert_eq!(unique_nickname(&players(&["Bob"]), "Alice"), "Alice");
+        assert_eq!(unique_nickname(&players(&["alice"]), "Alice"), "Alice (2)");
+        assert_eq!(
+            unique_nickname(&players(&["Alice", "Alice (2)"]), "Alice"),
+            "Alice (3)"
+        );
+    }
+
+    #[test]
+    fn test_unique_nickname_length() {
+        let nickname = "Sixteen Chars!!!";
+        assert_eq!(
+            unique_nickname(&players(&[nickname]), nickname),
+            "Sixteen Char (2)"
+        );
+        let mut taken = vec![nickname.to_string()];
+        taken.extend((2..10).map(|n| format!("Sixteen Char ({})", n)));
+        let taken: Vec<&str> = taken.iter().map(|x| x.as_str()).collect();
+        let rv = unique_nickname(&players(&taken), nickname);
+        assert_eq!(rv, "Sixteen Cha (10)");
+        assert_eq!(rv.chars().count(), MAX_NICKNAME_LENGTH);
+        // trailing spaces are not kept before the suffix
+        assert_eq!(
+            unique_nickname(&players(&["Twelve char  abc"]), "Twelve char  abc"),
+            "Twelve char (2)"
+        );
+    }
+}
@@ -0,0 +1,15 @@
+mod accounts;
+mod admin;
+mod board;
+mod bot;
+pub mod config;
+mod game;
+mod metrics;
+mod moderation;
+mod projection;
+mod reaper;
+pub mod server;
+mod universe;
+mod utils;
+
+pub(crate) use webgame_protocol as protocol;
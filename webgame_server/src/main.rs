@@ -1,13 +1,8 @@
-mod board;
-mod game;
-mod server;
-mod universe;
-mod utils;
-
-pub(crate) use webgame_protocol as protocol;
+use webgame_server::{config, server};
 
 #[tokio::main]
 pub async fn main() {
     pretty_env_logger::init();
-    server::serve().await;
+    let config = config::Config::from_env().expect("could not load config");
+    server::serve(config).await;
 }
@@ -0,0 +1,284 @@
+//! Counters exposed on the `/metrics` route in the Prometheus text format.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::protocol::{Command, Message, ProtocolErrorKind, Turn};
+
+/// The upper bounds of the serialization time buckets in seconds.
+const SERIALIZATION_BUCKETS: &[f64] = &[0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01];
+
+#[derive(Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+    connections: AtomicI64,
+    commands_received: Mutex<BTreeMap<&'static str, u64>>,
+    messages_sent: Mutex<BTreeMap<&'static str, u64>>,
+    errors: Mutex<BTreeMap<&'static str, u64>>,
+    turn_transitions: Mutex<BTreeMap<&'static str, u64>>,
+    serialization: Mutex<Histogram>,
+}
+
+/// Values that are collected from the universe when the metrics are read.
+pub struct UniverseStats {
+    pub authenticated_players: usize,
+    pub games: Vec<Turn>,
+}
+
+fn command_name(cmd: &Command) -> &'static str {
+    match *cmd {
+        Command::Hello(..) => "hello",
+        Command::Authenticate(..) => "authenticate",
+        Command::SendText(..) => "send_text",
+        Command::ShareCodename(..) => "share_codename",
+        Command::NewGame(..) => "new_game",
+        Command::ListGames => "list_games",
+        Command::JoinGame(..) => "join_game",
+        Command::LeaveGame => "leave_game",
+        Command::MarkReady => "mark_ready",
+        Command::SetPlayerRole(..) => "set_player_role",
+        Command::SetPlayerTeam(..) => "set_player_team",
+        Command::RevealCard(..) => "reveal_card",
+        Command::EndTurn => "end_turn",
+        Command::AddBot(..) => "add_bot",
+        Command::Register(..) => "register",
+        Command::RequestMagicToken(..) => "request_magic_token",
+        Command::GetStats => "get_stats",
+        Command::Rematch => "rematch",
+    }
+}
+
+/// Returns the label of a message as used in metrics and logs.
+pub fn message_name(message: &Message) -> &'static str {
+    match *message {
+        Message::Hello(..) => "hello",
+        Message::Ack(..) => "ack",
+        Message::Chat(..) => "chat",
+        Message::PlayerConnected(..) => "player_connected",
+        Message::PlayerDisconnected(..) => "player_disconnected",
+        Message::PregameStarted => "pregame_started",
+        Message::GameJoined(..) => "game_joined",
+        Message::GameLeft => "game_left",
+        Message::GameList(..) => "game_list",
+        Message::Authenticated(..) => "authenticated",
+        Message::Error(..) => "error",
+        Message::GameStateSnapshot(..) => "game_state_snapshot",
+        Message::ServerNotice(..) => "server_notice",
+        Message::ServerShutdown(..) => "server_shutdown",
+        Message::Stats(..) => "stats",
+    }
+}
+
+fn error_kind_name(kind: ProtocolErrorKind) -> &'static str {
+    match kind {
+        ProtocolErrorKind::AlreadyAuthenticated => "already_authenticated",
+        ProtocolErrorKind::NotAuthenticated => "not_authenticated",
+        ProtocolErrorKind::InvalidCommand => "invalid_command",
+        ProtocolErrorKind::BadState => "bad_state",
+        ProtocolErrorKind::NotFound => "not_found",
+        ProtocolErrorKind::BadInput => "bad_input",
+        ProtocolErrorKind::InternalError => "internal_error",
+        ProtocolErrorKind::UnsupportedVersion => "unsupported_version",
+    }
+}
+
+fn turn_name(turn: Turn) -> &'static str {
+    match turn {
+        Turn::Pregame => "pregame",
+        Turn::Intermission => "intermission",
+        Turn::RedSpymasterThinking => "red_spymaster_thinking",
+        Turn::BlueSpymasterThinking => "blue_spymaster_thinking",
+        Turn::RedOperativesGuessing => "red_operatives_guessing",
+        Turn::BlueOperativesGuessing => "blue_operatives_guessing",
+        Turn::Endgame => "endgame",
+    }
+}
+
+fn increment(map: &Mutex<BTreeMap<&'static str, u64>>, label: &'static str) {
+    *map.lock().unwrap().entry(label).or_insert(0) += 1;
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+}
+
+fn write_labeled(out: &mut String, name: &str, label: &str, values: &BTreeMap<&'static str, u64>) {
+    for (value, count) in values.iter() {
+        writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, value, count).unwrap();
+    }
+}
+
+impl Metrics {
+    pub fn connection_opened(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_closed(&self) {
+        self.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn command_received(&self, cmd: &Command) {
+        increment(&self.commands_received, command_name(cmd));
+    }
+
+    pub fn message_sent(&self, message: &Message, serialization_time: Duration) {
+        increment(&self.messages_sent, message_name(message));
+
+        let seconds = serialization_time.as_secs_f64();
+        let mut histogram = self.serialization.lock().unwrap();
+        if histogram.buckets.is_empty() {
+            histogram.buckets = vec![0; SERIALIZATION_BUCKETS.len()];
+        }
+        for (bucket, &bound) in histogram.buckets.iter_mut().zip(SERIALIZATION_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    pub fn error_sent(&self, kind: ProtocolErrorKind) {
+        increment(&self.errors, error_kind_name(kind));
+    }
+
+    pub fn turn_changed(&self, turn: Turn) {
+        increment(&self.turn_transitions, turn_name(turn));
+    }
+
+    /// Renders all metrics in the Prometheus text format.
+    pub fn render(&self, stats: &UniverseStats) -> String {
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "webgame_connections",
+            "gauge",
+            "Number of open websocket connections.",
+        );
+        writeln!(
+            out,
+            "webgame_connections {}",
+            self.connections.load(Ordering::Relaxed)
+        )
+        .unwrap();
+
+        write_header(
+            &mut out,
+            "webgame_authenticated_players",
+            "gauge",
+            "Number of authenticated players including bots.",
+        );
+        writeln!(
+            out,
+            "webgame_authenticated_players {}",
+            stats.authenticated_players
+        )
+        .unwrap();
+
+        write_header(
+            &mut out,
+            "webgame_games",
+            "gauge",
+            "Number of games by turn.",
+        );
+        let mut games = BTreeMap::new();
+        for &turn in stats.games.iter() {
+            *games.entry(turn_name(turn)).or_insert(0) += 1;
+        }
+        write_labeled(&mut out, "webgame_games", "turn", &games);
+
+        write_header(
+            &mut out,
+            "webgame_turn_transitions_total",
+            "counter",
+            "Number of times games moved to a turn.",
+        );
+        write_labeled(
+            &mut out,
+            "webgame_turn_transitions_total",
+            "turn",
+            &self.turn_transitions.lock().unwrap(),
+        );
+
+        write_header(
+            &mut out,
+            "webgame_commands_received_total",
+            "counter",
+            "Number of commands received from websockets.",
+        );
+        write_labeled(
+            &mut out,
+            "webgame_commands_received_total",
+            "cmd",
+            &self.commands_received.lock().unwrap(),
+        );
+
+        write_header(
+            &mut out,
+            "webgame_messages_sent_total",
+            "counter",
+            "Number of messages sent to players.",
+        );
+        write_labeled(
+            &mut out,
+            "webgame_messages_sent_total",
+            "type",
+            &self.messages_sent.lock().unwrap(),
+        );
+
+        write_header(
+            &mut out,
+            "webgame_errors_total",
+            "counter",
+            "Number of protocol errors returned to websockets.",
+        );
+        write_labeled(
+            &mut out,
+            "webgame_errors_total",
+            "kind",
+            &self.errors.lock().unwrap(),
+        );
+
+        write_header(
+            &mut out,
+            "webgame_serialization_seconds",
+            "histogram",
+            "Time spent serializing messages.",
+        );
+        let histogram = self.serialization.lock().unwrap();
+        for (idx, &bound) in SERIALIZATION_BUCKETS.iter().enumerate() {
+            writeln!(
+                out,
+                "webgame_serialization_seconds_bucket{{le=\"{}\"}} {}",
+                bound,
+                histogram.buckets.get(idx).copied().unwrap_or(0)
+            )
+            .unwrap();
+        }
+        writeln!(
+            out,
+            "webgame_serialization_seconds_bucket{{le=\"+Inf\"}} {}",
+            histogram.count
+        )
+        .unwrap();
+        writeln!(out, "webgame_serialization_seconds_sum {}", histogram.sum).unwrap();
+        writeln!(
+            out,
+            "webgame_serialization_seconds_count {}",
+            histogram.count
+        )
+        .unwrap();
+
+        out
+    }
+}
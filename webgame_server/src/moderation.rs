@@ -0,0 +1,96 @@
+use std::fs;
+use std::io;
+
+use regex::{RegexSet, RegexSetBuilder};
+
+use crate::config::ModerationConfig;
+
+/// A filter for user supplied text such as chat messages and nicknames.
+pub trait MessageFilter: Send + Sync {
+    /// Returns `true` if the text may be shown to other players.
+    fn is_allowed(&self, text: &str) -> bool;
+}
+
+/// The default filter which rejects text matching a list of patterns.
+pub struct BlocklistFilter {
+    patterns: RegexSet,
+}
+
+impl BlocklistFilter {
+    /// Creates a filter from a list of regular expressions.
+    pub fn new<I, S>(patterns: I) -> Result<BlocklistFilter, regex::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Ok(BlocklistFilter {
+            patterns: RegexSetBuilder::new(patterns)
+                .case_insensitive(true)
+                .build()?,
+        })
+    }
+
+    /// Creates the filter from the moderation config.
+    pub fn from_config(config: &ModerationConfig) -> Result<BlocklistFilter, io::Error> {
+        let mut patterns = config.blocklist.clone();
+        if let Some(ref path) = config.blocklist_file {
+            patterns.extend(
+                fs::read_to_string(path)?
+                    .lines()
+                    .map(|x| x.trim())
+                    .filter(|x| !x.is_empty() && !x.starts_with('#'))
+                    .map(|x| x.to_string()),
+            );
+        }
+        BlocklistFilter::new(patterns)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl MessageFilter for BlocklistFilter {
+    fn is_allowed(&self, text: &str) -> bool {
+        !self.patterns.is_match(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use uuid::Uuid;
+
+    #[test]
+    fn test_blocklist() {
+        let filter = BlocklistFilter::new(&[r"\bbad\s*word\b", "^spam"]).unwrap();
+        assert!(filter.is_allowed("a perfectly fine message"));
+        assert!(!filter.is_allowed("this is a BAD word"));
+        assert!(!filter.is_allowed("badword"));
+        assert!(!filter.is_allowed("spam spam spam"));
+        assert!(filter.is_allowed("no spam here"));
+        assert!(BlocklistFilter::new(&["("]).is_err());
+    }
+
+    #[test]
+    fn test_blocklist_from_config() {
+        let path = env::temp_dir().join(format!("webgame-blocklist-{}", Uuid::new_v4()));
+        fs::write(&path, "# comments are ignored\n\n  rude  \n").unwrap();
+        let config = ModerationConfig {
+            blocklist: vec!["insult".into()],
+            blocklist_file: Some(path.clone()),
+        };
+        let filter = BlocklistFilter::from_config(&config);
+        fs::remove_file(&path).ok();
+        let filter = filter.unwrap();
+        assert!(!filter.is_allowed("an insult"));
+        assert!(!filter.is_allowed("so rude"));
+        assert!(filter.is_allowed("comments are ignored"));
+
+        let config = ModerationConfig {
+            blocklist: vec![],
+            blocklist_file: Some(path),
+        };
+        assert!(BlocklistFilter::from_config(&config).is_err());
+    }
+}
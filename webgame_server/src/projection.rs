@@ -0,0 +1,135 @@
+use crate::protocol::{GamePlayerState, PlayerRole, SpectatorVisibility, Turn};
+
+/// Decides if a viewer may see the full key card.
+///
+/// Viewers that are not allowed to see the key card only get to see the
+/// characters of tiles which were already spotted.  Operatives see the key
+/// card once the game is over.  `is_suspect` is set for spectators that
+/// share a session or address with a seated player.
+pub fn can_see_key(
+    viewer: &GamePlayerState,
+    turn: Turn,
+    spectator_visibility: SpectatorVisibility,
+    is_suspect: bool,
+) -> bool {
+    if turn == Turn::Pregame {
+        return false;
+    }
+    match viewer.role {
+        PlayerRole::Spymaster => true,
+        PlayerRole::Operative => turn == Turn::Endgame,
+        PlayerRole::Spectator if is_suspect => false,
+        PlayerRole::Spectator => match spectator_visibility {
+            SpectatorVisibility::Full => true,
+            SpectatorVisibility::RevealedOnly => false,
+            SpectatorVisibility::Delayed => turn == Turn::Endgame,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    use crate::protocol::{PlayerInfo, Team};
+
+    fn viewer(team: Option<Team>, role: PlayerRole) -> GamePlayerState {
+        GamePlayerState {
+            player: PlayerInfo {
+                id: Uuid::new_v4(),
+                nickname: "viewer".into(),
+                is_bot: false,
+                account: None,
+            },
+            team,
+            role,
+            ready: true,
+        }
+    }
+
+    #[test]
+    fn test_nobody_sees_key_in_pregame() {
+        for &role in &[
+            PlayerRole::Spymaster,
+            PlayerRole::Operative,
+            PlayerRole::Spectator,
+        ] {
+            let viewer = viewer(Some(Team::Red), role);
+            assert!(!can_see_key(
+                &viewer,
+                Turn::Pregame,
+                SpectatorVisibility::Full,
+                false
+            ));
+        }
+    }
+
+    #[test]
+    fn test_seated_players() {
+        let spymaster = viewer(Some(Team::Blue), PlayerRole::Spymaster);
+        let operative = viewer(Some(Team::Blue), PlayerRole::Operative);
+        for &turn in &[Turn::RedOperativesGuessing, Turn::BlueSpymasterThinking] {
+            assert!(can_see_key(
+                &spymaster,
+                turn,
+                SpectatorVisibility::RevealedOnly,
+                false
+            ));
+            assert!(!can_see_key(
+                &operative,
+                turn,
+                SpectatorVisibility::Full,
+                false
+            ));
+        }
+        assert!(can_see_key(
+            &operative,
+            Turn::Endgame,
+            SpectatorVisibility::RevealedOnly,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_spectator_visibility() {
+        let spectator = viewer(None, PlayerRole::Spectator);
+        let turn = Turn::RedSpymasterThinking;
+        assert!(can_see_key(
+            &spectator,
+            turn,
+            SpectatorVisibility::Full,
+            false
+        ));
+        assert!(!can_see_key(
+            &spectator,
+            turn,
+            SpectatorVisibility::RevealedOnly,
+            false
+        ));
+        assert!(!can_see_key(
+            &spectator,
+            turn,
+            SpectatorVisibility::Delayed,
+            false
+        ));
+        assert!(can_see_key(
+            &spectator,
+            Turn::Endgame,
+            SpectatorVisibility::Delayed,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_suspect_spectators_are_downgraded() {
+        let spectator = viewer(None, PlayerRole::Spectator);
+        assert!(!can_see_key(
+            &spectator,
+            Turn::BlueOperativesGuessing,
+            SpectatorVisibility::Full,
+            true
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+//! Background task that keeps connections alive and closes idle games.
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::time;
+
+use crate::protocol::{Message, ServerNoticeMessage, Turn};
+use crate::universe::Universe;
+
+/// Closes games nobody did anything in for too long.
+async fn close_idle_games(universe: &Universe) {
+    let timeouts = &universe.config().timeouts;
+    for game in universe.get_games().await {
+        let max_idle = if game.turn().await == Turn::Endgame {
+            timeouts.finished_game_idle_secs
+        } else {
+            timeouts.game_idle_secs
+        };
+        if game.idle_time().await < Duration::from_secs(max_idle) {
+            continue;
+        }
+        log::info!("closing idle game {}", game.id());
+        game.broadcast(&Message::ServerNotice(ServerNoticeMessage {
+            text: "The game was closed because nobody played for a while.".into(),
+        }))
+        .await;
+        game.close().await;
+    }
+}
+
+/// Runs the reaper until the server shuts down.
+pub async fn run(universe: Arc<Universe>) {
+    let interval_secs = universe.config().timeouts.heartbeat_interval_secs.max(1);
+    let mut interval = time::interval(Duration::from_secs(interval_secs));
+    loop {
+        interval.tick().await;
+        universe.ping_all().await;
+        close_idle_games(&universe).await;
+    }
+}
@@ -1,20 +1,65 @@
 use std::convert::Infallible;
+use std::fs;
+use std::future::Future;
+use std::net::{IpAddr, TcpListener};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use futures::{FutureExt, StreamExt};
+use hyper::server::conn::AddrStream;
 use hyper::{service::make_service_fn, Server};
-use tokio::sync::mpsc;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tokio::signal;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time;
 use uuid::Uuid;
+use warp::http::StatusCode;
 use warp::{ws, Filter};
 
+use crate::accounts::{Account, AccountStore};
+use crate::admin;
+use crate::bot;
+use crate::config::Config;
+use crate::game::MAX_NICKNAME_LENGTH;
+use crate::moderation::BlocklistFilter;
 use crate::protocol::{
-    AuthenticateCommand, ChatMessage, Command, JoinGameCommand, Message, ProtocolError,
-    ProtocolErrorKind, RevealCardCommand, SendTextCommand, SetPlayerRoleCommand,
-    SetPlayerTeamCommand, ShareCodenameCommand,
+    AckMessage, AddBotCommand, AuthenticateCommand, Command, CommandEnvelope, Credentials,
+    Encoding, ErrorCode, GameListMessage, HelloCommand, HelloMessage, JoinGameCommand,
+    LeaderboardEntry, Message, NewGameCommand, PlayerRole, ProtocolError, RegisterCommand,
+    RequestMagicTokenCommand, RevealCardCommand, SendTextCommand, ServerShutdownMessage,
+    SetPlayerRoleCommand, SetPlayerTeamCommand, ShareCodenameCommand, StatsMessage,
+    PROTOCOL_VERSION,
 };
+use crate::reaper;
 use crate::universe::Universe;
 
-async fn on_player_connected(universe: Arc<Universe>, ws: ws::WebSocket) {
+/// The reason sent in the websocket close frame on shutdown.
+const SHUTDOWN_CLOSE_REASON: &str = "server shutting down";
+
+/// How long to wait for clients to finish the close handshake.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The protocol versions this server can talk.
+const SUPPORTED_PROTOCOL_VERSIONS: &[u32] = &[PROTOCOL_VERSION];
+
+/// The optional features announced to clients.
+const FEATURES: &[&str] = &[
+    "bots",
+    "chat_channels",
+    "end_turn",
+    "rematch",
+    "request_ids",
+    "spectator_visibility",
+];
+
+/// The optional features that need the account database.
+const ACCOUNT_FEATURES: &[&str] = &["accounts", "stats"];
+
+/// The number of players shown on the leaderboard.
+const LEADERBOARD_SIZE: usize = 20;
+
+async fn on_player_connected(universe: Arc<Universe>, ws: ws::WebSocket, remote_addr: IpAddr) {
     let (user_ws_tx, mut user_ws_rx) = ws.split();
     let (tx, rx) = mpsc::unbounded_channel();
 
@@ -24,14 +69,33 @@ async fn on_player_connected(universe: Arc<Universe>, ws: ws::WebSocket) {
         }
     }));
 
-    let player_id = universe.add_player(tx).await;
+    if universe.is_shutting_down() {
+        tx.send(Ok(ws::Message::close_with(1001u16, SHUTDOWN_CLOSE_REASON)))
+            .ok();
+        return;
+    }
+
+    let player_id = universe.add_player(tx, Some(remote_addr)).await;
+    universe.metrics().connection_opened();
     log::info!("player {:#?} connected", player_id);
 
-    while let Some(result) = user_ws_rx.next().await {
+    let heartbeat_timeout = Duration::from_secs(universe.config().timeouts.heartbeat_timeout_secs);
+    loop {
+        let result = match time::timeout(heartbeat_timeout, user_ws_rx.next()).await {
+            Ok(Some(result)) => result,
+            Ok(None) => break,
+            Err(_) => {
+                log::info!("player {} timed out", player_id);
+                break;
+            }
+        };
         match result {
+            // pongs only keep the connection alive.
+            Ok(msg) if msg.is_ping() || msg.is_pong() => {}
             Ok(msg) => {
                 log::debug!("Got message from websocket: {:?}", &msg);
                 if let Err(err) = on_player_message(universe.clone(), player_id, msg).await {
+                    universe.metrics().error_sent(err.kind());
                     universe.send(player_id, &Message::Error(err)).await;
                 }
             }
@@ -42,6 +106,7 @@ async fn on_player_connected(universe: Arc<Universe>, ws: ws::WebSocket) {
         }
     }
 
+    universe.metrics().connection_closed();
     on_player_disconnected(universe, player_id).await;
 }
 
@@ -53,44 +118,106 @@ async fn on_player_disconnected(universe: Arc<Universe>, player_id: Uuid) {
     log::info!("user {:#?} disconnected", player_id);
 }
 
+/// Used to find the request ID of commands that failed to decode.
+#[derive(Deserialize)]
+struct RequestIdOnly {
+    #[serde(default)]
+    request_id: Option<u64>,
+}
+
+/// Decodes a text frame as JSON or a binary frame as MessagePack.
+fn decode_frame<T: DeserializeOwned>(
+    msg: &ws::Message,
+    encoding: Encoding,
+) -> Result<T, ProtocolError> {
+    let result = if let Ok(text) = msg.to_str() {
+        serde_json::from_str(text).map_err(|err| err.to_string())
+    } else if msg.is_binary() {
+        if encoding != Encoding::Msgpack {
+            return Err(ProtocolError::new(
+                ErrorCode::EncodingNotNegotiated,
+                "binary frames require the msgpack encoding",
+            ));
+        }
+        rmp_serde::from_read_ref(msg.as_bytes()).map_err(|err| err.to_string())
+    } else {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidFrame,
+            "not a valid text or binary frame",
+        ));
+    };
+    result.map_err(|err| ProtocolError::new(ErrorCode::MalformedCommand, err))
+}
+
 async fn on_player_message(
     universe: Arc<Universe>,
     player_id: Uuid,
     msg: ws::Message,
 ) -> Result<(), ProtocolError> {
-    let req_json = match msg.to_str() {
-        Ok(text) => text,
-        Err(()) => {
-            return Err(ProtocolError::new(
-                ProtocolErrorKind::InvalidCommand,
-                "not a valid text frame",
-            ))
-        }
-    };
-
-    let cmd: Command = match serde_json::from_str(&req_json) {
-        Ok(req) => req,
+    let encoding = universe.get_encoding(player_id).await;
+    let envelope: CommandEnvelope = match decode_frame(&msg, encoding) {
+        Ok(envelope) => envelope,
         Err(err) => {
-            return Err(ProtocolError::new(
-                ProtocolErrorKind::InvalidCommand,
-                err.to_string(),
-            ));
+            // tell the client which command was rejected if we can.
+            let request_id = decode_frame::<RequestIdOnly>(&msg, encoding)
+                .ok()
+                .and_then(|x| x.request_id);
+            return Err(err.with_request_id(request_id));
         }
     };
+    universe.metrics().command_received(&envelope.command);
 
+    let request_id = envelope.request_id;
+    on_player_command(universe.clone(), player_id, envelope.command)
+        .await
+        .map_err(|err| err.with_request_id(request_id))?;
+    if let Some(request_id) = request_id {
+        universe
+            .send(player_id, &Message::Ack(AckMessage { request_id }))
+            .await;
+    }
+    Ok(())
+}
+
+/// Handles a command from a player.
+///
+/// This is also used by bots which send their commands directly instead of
+/// going through a websocket.
+pub async fn on_player_command(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    cmd: Command,
+) -> Result<(), ProtocolError> {
     log::debug!("command: {:?}", &cmd);
 
     if !universe.player_is_authenticated(player_id).await {
         match cmd {
+            Command::Hello(data) => on_player_hello(universe, player_id, data).await,
+            Command::Authenticate(_) | Command::Register(_) | Command::RequestMagicToken(_)
+                if universe.get_protocol_version(player_id).await.is_none() =>
+            {
+                Err(ProtocolError::new(
+                    ErrorCode::HelloRequired,
+                    "client did not say hello, please reload",
+                ))
+            }
             Command::Authenticate(data) => on_player_authenticate(universe, player_id, data).await,
+            Command::Register(data) => on_player_register(universe, player_id, data).await,
+            Command::RequestMagicToken(data) => {
+                on_request_magic_token(universe, player_id, data).await
+            }
             _ => Err(ProtocolError::new(
-                ProtocolErrorKind::NotAuthenticated,
+                ErrorCode::NotAuthenticated,
                 "cannot perform this command unauthenticated",
             )),
         }
     } else {
+        if let Some(game) = universe.get_player_game(player_id).await {
+            game.touch().await;
+        }
         match cmd {
-            Command::NewGame => on_new_game(universe, player_id).await,
+            Command::NewGame(cmd) => on_new_game(universe, player_id, cmd).await,
+            Command::ListGames => on_list_games(universe, player_id).await,
             Command::JoinGame(cmd) => on_join_game(universe, player_id, cmd).await,
             Command::LeaveGame => on_leave_game(universe, player_id).await,
             Command::MarkReady => on_player_mark_ready(universe, player_id).await,
@@ -99,19 +226,37 @@ async fn on_player_message(
             Command::SetPlayerRole(cmd) => on_player_set_role(universe, player_id, cmd).await,
             Command::SetPlayerTeam(cmd) => on_player_set_team(universe, player_id, cmd).await,
             Command::RevealCard(cmd) => on_player_reveal_card(universe, player_id, cmd).await,
+            Command::EndTurn => on_player_end_turn(universe, player_id).await,
+            Command::AddBot(cmd) => on_add_bot(universe, player_id, cmd).await,
+            Command::GetStats => on_get_stats(universe, player_id).await,
+            Command::Rematch => on_player_rematch(universe, player_id).await,
 
             // this should not happen here.
             Command::Authenticate(..) => Err(ProtocolError::new(
-                ProtocolErrorKind::AlreadyAuthenticated,
+                ErrorCode::AlreadyAuthenticated,
                 "cannot authenticate twice",
             )),
+            Command::Hello(..) => Err(ProtocolError::new(
+                ErrorCode::AlreadySaidHello,
+                "cannot say hello after authenticating",
+            )),
+            Command::Register(..) | Command::RequestMagicToken(..) => Err(ProtocolError::new(
+                ErrorCode::AlreadyAuthenticated,
+                "cannot use accounts after authenticating",
+            )),
         }
     }
 }
 
-async fn on_new_game(universe: Arc<Universe>, player_id: Uuid) -> Result<(), ProtocolError> {
+async fn on_new_game(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    cmd: NewGameCommand,
+) -> Result<(), ProtocolError> {
     universe.remove_player_from_game(player_id).await;
-    let game = universe.new_game().await;
+    let game = universe
+        .new_game(cmd.public, cmd.mode, cmd.spectator_visibility)
+        .await;
     game.add_player(player_id).await;
     universe
         .send(player_id, &Message::GameJoined(game.game_info()))
@@ -120,6 +265,14 @@ async fn on_new_game(universe: Arc<Universe>, player_id: Uuid) -> Result<(), Pro
     Ok(())
 }
 
+async fn on_list_games(universe: Arc<Universe>, player_id: Uuid) -> Result<(), ProtocolError> {
+    let games = universe.list_public_games().await;
+    universe
+        .send(player_id, &Message::GameList(GameListMessage { games }))
+        .await;
+    Ok(())
+}
+
 async fn on_join_game(
     universe: Arc<Universe>,
     player_id: Uuid,
@@ -130,6 +283,7 @@ async fn on_join_game(
         .send(player_id, &Message::GameJoined(game.game_info()))
         .await;
     game.broadcast_state().await;
+    game.send_chat_history(player_id).await;
     Ok(())
 }
 
@@ -139,24 +293,260 @@ async fn on_leave_game(universe: Arc<Universe>, player_id: Uuid) -> Result<(), P
     Ok(())
 }
 
+/// Checks if a character would not be visible in a nickname.
+fn is_invisible_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            // soft hyphen, combining grapheme joiner and mongolian vowel
+            // separator
+            '\u{ad}' | '\u{34f}' | '\u{180e}'
+            // hangul fillers
+            | '\u{115f}' | '\u{1160}' | '\u{3164}' | '\u{ffa0}'
+            // zero width spaces, joiners and directional marks
+            | '\u{200b}'..='\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            // word joiner, invisible operators and directional isolates
+            | '\u{2060}'..='\u{2069}'
+            // byte order mark
+            | '\u{feff}'
+        )
+}
+
+async fn on_player_hello(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    cmd: HelloCommand,
+) -> Result<(), ProtocolError> {
+    if !SUPPORTED_PROTOCOL_VERSIONS.contains(&cmd.protocol_version) {
+        log::info!(
+            "player {:?} uses unsupported protocol version {} ({:?})",
+            player_id,
+            cmd.protocol_version,
+            &cmd.client_name
+        );
+        return Err(ProtocolError::new(
+            ErrorCode::UnsupportedVersion,
+            format!(
+                "protocol version {} is not supported by this server, please reload",
+                cmd.protocol_version
+            ),
+        )
+        .with_supported_versions(SUPPORTED_PROTOCOL_VERSIONS.to_vec()));
+    }
+
+    let encoding = cmd
+        .encodings
+        .iter()
+        .find_map(|x| Encoding::from_name(x))
+        .unwrap_or_default();
+    universe
+        .set_protocol_version(player_id, cmd.protocol_version, encoding)
+        .await?;
+    log::info!(
+        "player {:?} speaks protocol version {} with {} ({:?})",
+        player_id,
+        cmd.protocol_version,
+        encoding.name(),
+        &cmd.client_name
+    );
+
+    universe
+        .send(
+            player_id,
+            &Message::Hello(HelloMessage {
+                protocol_version: cmd.protocol_version,
+                supported_versions: SUPPORTED_PROTOCOL_VERSIONS.to_vec(),
+                features: FEATURES
+                    .iter()
+                    .copied()
+                    .chain(
+                        if universe.has_accounts() {
+                            ACCOUNT_FEATURES
+                        } else {
+                            &[]
+                        }
+                        .iter()
+                        .copied(),
+                    )
+                    .map(|x| x.to_string())
+                    .collect(),
+                encoding,
+            }),
+        )
+        .await;
+
+    Ok(())
+}
+
+/// Runs an account operation on the thread pool for blocking tasks.
+///
+/// Account operations hash passwords and access the database so they
+/// must not run on the executor.
+async fn run_blocking<F, T>(f: F) -> Result<T, ProtocolError>
+where
+    F: FnOnce() -> Result<T, ProtocolError> + Send + 'static,
+    T: Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(rv) => rv,
+        Err(err) => {
+            log::error!("blocking task failed: {}", err);
+            Err(ProtocolError::new(
+                ErrorCode::ServerError,
+                "internal server error",
+            ))
+        }
+    }
+}
+
+async fn on_player_register(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    cmd: RegisterCommand,
+) -> Result<(), ProtocolError> {
+    let accounts = universe.accounts()?;
+    check_rate_limit(&universe, player_id).await?;
+    let username = cmd.username.trim().to_owned();
+    let password = cmd.password.filter(|x| !x.is_empty());
+    let email = cmd
+        .email
+        .map(|x| x.trim().to_owned())
+        .filter(|x| !x.is_empty());
+    run_blocking(move || {
+        accounts
+            .register(&username, password.as_deref(), email.as_deref())
+            .map(|_| ())
+    })
+    .await
+}
+
+async fn on_request_magic_token(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    cmd: RequestMagicTokenCommand,
+) -> Result<(), ProtocolError> {
+    let accounts = universe.accounts()?;
+    check_rate_limit(&universe, player_id).await?;
+    run_blocking(move || accounts.send_magic_token(cmd.username.trim())).await
+}
+
+/// Checks that a nickname can be shown to other players.
+fn check_nickname(universe: &Universe, nickname: &str) -> Result<(), ProtocolError> {
+    if nickname.is_empty() || nickname.chars().count() > MAX_NICKNAME_LENGTH {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidNicknameLength,
+            format!(
+                "nickname must be between 1 and {} characters",
+                MAX_NICKNAME_LENGTH
+            ),
+        )
+        .with_field("nickname")
+        .with_limit(MAX_NICKNAME_LENGTH as u64));
+    }
+    if nickname.chars().any(is_invisible_char) {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidNicknameCharacters,
+            "nickname must not contain control or invisible characters",
+        )
+        .with_field("nickname"));
+    }
+    if !universe.filter().is_allowed(nickname) {
+        return Err(ProtocolError::new(
+            ErrorCode::NicknameNotAllowed,
+            "this nickname is not allowed",
+        )
+        .with_field("nickname"));
+    }
+    Ok(())
+}
+
+/// Signs in to an account and returns it with the nickname to use.
+///
+/// Without a nickname the one of the account is picked.  It is checked
+/// before a magic token is used up so that the player can try again.
+fn sign_in(
+    universe: &Universe,
+    accounts: &AccountStore,
+    credentials: Credentials,
+    nickname: String,
+) -> Result<(Account, String), ProtocolError> {
+    let account_nickname = |account: &Account| -> Result<String, ProtocolError> {
+        if !nickname.is_empty() {
+            return Ok(nickname.clone());
+        }
+        let nickname = match account.nickname {
+            Some(ref nickname) => nickname.clone(),
+            None => account.username.chars().take(MAX_NICKNAME_LENGTH).collect(),
+        };
+        check_nickname(universe, &nickname)?;
+        Ok(nickname)
+    };
+    match credentials {
+        Credentials::Password { username, password } => {
+            let account = accounts.sign_in_with_password(username.trim(), &password)?;
+            let nickname = account_nickname(&account)?;
+            Ok((account, nickname))
+        }
+        Credentials::MagicToken { token } => {
+            let account = accounts.find_magic_token(token.trim())?;
+            let nickname = account_nickname(&account)?;
+            accounts.redeem_magic_token(token.trim())?;
+            Ok((account, nickname))
+        }
+    }
+}
+
 async fn on_player_authenticate(
     universe: Arc<Universe>,
     player_id: Uuid,
     cmd: AuthenticateCommand,
 ) -> Result<(), ProtocolError> {
+    // signing in can use up a magic token so the nickname is checked first.
+    // An empty nickname picks the one of the account.
     let nickname = cmd.nickname.trim().to_owned();
-    if nickname.is_empty() || nickname.len() > 16 {
-        return Err(ProtocolError::new(
-            ProtocolErrorKind::BadInput,
-            "nickname must be between 1 and 16 characters",
-        ));
+    if cmd.credentials.is_none() || !nickname.is_empty() {
+        check_nickname(&universe, &nickname)?;
     }
 
-    let player_info = universe.authenticate_player(player_id, nickname).await?;
+    let (account, nickname) = match cmd.credentials {
+        Some(credentials) => {
+            let accounts = universe.accounts()?;
+            check_rate_limit(&universe, player_id).await?;
+            let universe = universe.clone();
+            let (account, nickname) =
+                run_blocking(move || sign_in(&universe, &accounts, credentials, nickname)).await?;
+            (Some(account), nickname)
+        }
+        None => (None, nickname),
+    };
+
+    // the session token is only used to detect players peeking at the
+    // key card from another tab, so ignore anything that looks off.
+    let session_token = cmd.session_token.filter(|x| !x.is_empty() && x.len() <= 64);
+
+    if let Some(ref account) = account {
+        if account.nickname.as_ref() != Some(&nickname) {
+            let accounts = universe.accounts()?;
+            let username = account.username.clone();
+            let nickname = nickname.clone();
+            run_blocking(move || accounts.set_nickname(&username, &nickname)).await?;
+        }
+    }
+
+    let player_info = universe
+        .authenticate_player(
+            player_id,
+            nickname,
+            session_token,
+            account.map(|x| x.username),
+        )
+        .await?;
     log::info!(
-        "player {:?} authenticated as {:?}",
+        "player {:?} authenticated as {:?} (account {:?})",
         player_id,
-        &player_info.nickname
+        &player_info.nickname,
+        &player_info.account
     );
 
     universe
@@ -166,12 +556,32 @@ async fn on_player_authenticate(
     Ok(())
 }
 
+async fn on_get_stats(universe: Arc<Universe>, player_id: Uuid) -> Result<(), ProtocolError> {
+    let accounts = universe.accounts()?;
+    let account = universe
+        .get_player_info(player_id)
+        .await
+        .and_then(|x| x.account);
+    let message = run_blocking(move || {
+        Ok(StatsMessage {
+            stats: match account {
+                Some(username) => Some(accounts.get_stats(&username)?),
+                None => None,
+            },
+            leaderboard: accounts.leaderboard(LEADERBOARD_SIZE)?,
+        })
+    })
+    .await?;
+    universe.send(player_id, &Message::Stats(message)).await;
+    Ok(())
+}
+
 pub async fn on_player_mark_ready(
     universe: Arc<Universe>,
     player_id: Uuid,
 ) -> Result<(), ProtocolError> {
     if let Some(game) = universe.get_player_game(player_id).await {
-        if game.is_joinable().await {
+        if game.is_pregame().await {
             game.mark_player_ready(player_id).await;
             game.broadcast_state().await;
         }
@@ -179,23 +589,57 @@ pub async fn on_player_mark_ready(
     Ok(())
 }
 
+/// Validates a piece of text a player wants to show to other players.
+///
+/// `field` names the command field the text came from.
+fn check_player_text(universe: &Universe, field: &str, text: &str) -> Result<(), ProtocolError> {
+    let max_length = universe.config().chat.max_message_length;
+    if text.chars().count() > max_length {
+        return Err(ProtocolError::new(
+            ErrorCode::TextTooLong,
+            format!("message must not be longer than {} characters", max_length),
+        )
+        .with_field(field)
+        .with_limit(max_length as u64));
+    }
+    if !universe.filter().is_allowed(text) {
+        return Err(ProtocolError::new(
+            ErrorCode::TextNotAllowed,
+            "message was rejected by the filter",
+        )
+        .with_field(field));
+    }
+    Ok(())
+}
+
+/// Takes a token out of the player's rate limit.
+///
+/// The limit is shared by chat messages and account commands which hash
+/// passwords or send mails.  For chat messages this should be the last
+/// check so that rejected messages do not count.
+async fn check_rate_limit(universe: &Universe, player_id: Uuid) -> Result<(), ProtocolError> {
+    if let Err(retry_after) = universe.take_chat_token(player_id).await {
+        return Err(ProtocolError::new(
+            ErrorCode::RateLimited,
+            "you are sending requests too quickly",
+        )
+        .with_retry_after_ms(retry_after.as_millis() as u64));
+    }
+    Ok(())
+}
+
 pub async fn on_player_send_text(
     universe: Arc<Universe>,
     player_id: Uuid,
     cmd: SendTextCommand,
 ) -> Result<(), ProtocolError> {
     if let Some(game) = universe.get_player_game(player_id).await {
-        game.broadcast(&Message::Chat(ChatMessage {
-            player_id,
-            text: cmd.text,
-        }))
-        .await;
-        Ok(())
+        check_player_text(&universe, "text", &cmd.text)?;
+        game.can_send_chat(player_id, cmd.channel).await?;
+        check_rate_limit(&universe, player_id).await?;
+        game.send_chat(player_id, cmd.text, cmd.channel).await
     } else {
-        Err(ProtocolError::new(
-            ProtocolErrorKind::BadState,
-            "not in a game",
-        ))
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
     }
 }
 
@@ -205,17 +649,18 @@ pub async fn on_player_share_codename(
     cmd: ShareCodenameCommand,
 ) -> Result<(), ProtocolError> {
     if let Some(game) = universe.get_player_game(player_id).await {
-        game.broadcast(&Message::Chat(ChatMessage {
-            player_id,
-            text: format!("codename: {} {}", cmd.codename, cmd.number),
-        }))
-        .await;
+        check_player_text(&universe, "codename", &cmd.codename)?;
+        check_rate_limit(&universe, player_id).await?;
+        let number = if cmd.unlimited {
+            None
+        } else {
+            Some(cmd.number)
+        };
+        game.share_codename(player_id, cmd.codename, number).await?;
+        game.broadcast_state().await;
         Ok(())
     } else {
-        Err(ProtocolError::new(
-            ProtocolErrorKind::BadState,
-            "not in a game",
-        ))
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
     }
 }
 
@@ -225,20 +670,17 @@ pub async fn on_player_set_role(
     cmd: SetPlayerRoleCommand,
 ) -> Result<(), ProtocolError> {
     if let Some(game) = universe.get_player_game(player_id).await {
-        if !game.is_joinable().await {
+        if !game.is_pregame().await {
             return Err(ProtocolError::new(
-                ProtocolErrorKind::BadState,
-                "cannot set role because game is not not joinable",
+                ErrorCode::GameAlreadyStarted,
+                "cannot set role because the game already started",
             ));
         }
         game.set_player_role(player_id, cmd.role).await;
         game.broadcast_state().await;
         Ok(())
     } else {
-        Err(ProtocolError::new(
-            ProtocolErrorKind::BadState,
-            "not in a game",
-        ))
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
     }
 }
 
@@ -248,20 +690,17 @@ pub async fn on_player_set_team(
     cmd: SetPlayerTeamCommand,
 ) -> Result<(), ProtocolError> {
     if let Some(game) = universe.get_player_game(player_id).await {
-        if !game.is_joinable().await {
+        if !game.is_pregame().await {
             return Err(ProtocolError::new(
-                ProtocolErrorKind::BadState,
-                "cannot set team because game is not not joinable",
+                ErrorCode::GameAlreadyStarted,
+                "cannot set team because the game already started",
             ));
         }
         game.set_player_team(player_id, cmd.team).await;
         game.broadcast_state().await;
         Ok(())
     } else {
-        Err(ProtocolError::new(
-            ProtocolErrorKind::BadState,
-            "not in a game",
-        ))
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
     }
 }
 
@@ -271,31 +710,263 @@ pub async fn on_player_reveal_card(
     cmd: RevealCardCommand,
 ) -> Result<(), ProtocolError> {
     if let Some(game) = universe.get_player_game(player_id).await {
-        // reveal
+        game.reveal_card(player_id, cmd.index).await?;
+        game.broadcast_state().await;
+        Ok(())
+    } else {
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
+    }
+}
+
+pub async fn on_player_rematch(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+) -> Result<(), ProtocolError> {
+    if let Some(game) = universe.get_player_game(player_id).await {
+        game.rematch(player_id).await?;
+        game.broadcast_state().await;
+        Ok(())
+    } else {
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
     }
-    Ok(())
 }
 
-pub async fn serve() {
-    let universe = Arc::new(Universe::new());
+pub async fn on_player_end_turn(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+) -> Result<(), ProtocolError> {
+    if let Some(game) = universe.get_player_game(player_id).await {
+        game.end_turn(player_id).await?;
+        game.broadcast_state().await;
+        Ok(())
+    } else {
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
+    }
+}
+
+async fn on_http_metrics(universe: Arc<Universe>) -> Result<impl warp::Reply, Infallible> {
+    let stats = universe.stats().await;
+    Ok(warp::reply::with_header(
+        universe.metrics().render(&stats),
+        "content-type",
+        "text/plain; version=0.0.4",
+    ))
+}
+
+async fn on_http_list_games(universe: Arc<Universe>) -> Result<impl warp::Reply, Infallible> {
+    Ok(warp::reply::json(&universe.list_public_games().await))
+}
+
+async fn on_http_leaderboard(universe: Arc<Universe>) -> Result<impl warp::Reply, Infallible> {
+    let leaderboard = match universe.accounts() {
+        Ok(accounts) => run_blocking(move || accounts.leaderboard(LEADERBOARD_SIZE)).await,
+        Err(_) => Ok(vec![]),
+    };
+    Ok(match leaderboard {
+        Ok(leaderboard) => {
+            warp::reply::with_status(warp::reply::json(&leaderboard), StatusCode::OK)
+        }
+        Err(_) => warp::reply::with_status(
+            warp::reply::json(&Vec::<LeaderboardEntry>::new()),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    })
+}
+
+pub async fn on_add_bot(
+    universe: Arc<Universe>,
+    player_id: Uuid,
+    cmd: AddBotCommand,
+) -> Result<(), ProtocolError> {
+    if let Some(game) = universe.get_player_game(player_id).await {
+        if !game.is_pregame().await {
+            return Err(ProtocolError::new(
+                ErrorCode::GameAlreadyStarted,
+                "cannot add a bot because the game already started",
+            ));
+        }
+        if cmd.role == PlayerRole::Spectator {
+            return Err(ProtocolError::new(
+                ErrorCode::BotsCannotSpectate,
+                "bots cannot be spectators",
+            )
+            .with_field("role"));
+        }
+        bot::spawn_bot(
+            universe,
+            game.join_code().to_string(),
+            cmd.team,
+            cmd.role,
+            cmd.difficulty,
+        );
+        Ok(())
+    } else {
+        Err(ProtocolError::new(ErrorCode::NotInGame, "not in a game"))
+    }
+}
+
+/// Resolves once the process was asked to stop with ctrl-c or `SIGTERM`.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("could not listen for SIGTERM");
+        tokio::select! {
+            _ = signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        signal::ctrl_c().await.ok();
+    }
+}
+
+/// Writes all games to the configured directory or discards them.
+async fn persist_games(universe: &Universe) {
+    let dir = match universe.config().shutdown.persist_dir {
+        Some(ref dir) => dir,
+        None => {
+            for game in universe.get_games().await {
+                log::info!("discarding game {} ({:?})", game.id(), game.turn().await);
+            }
+            return;
+        }
+    };
+    if let Err(err) = fs::create_dir_all(dir) {
+        log::error!("could not create {}: {}", dir.display(), err);
+        return;
+    }
+    for game in universe.get_games().await {
+        match game.persist(dir).await {
+            Ok(()) => log::info!("persisted game {}", game.id()),
+            Err(err) => log::error!("could not persist game {}: {}", game.id(), err),
+        }
+    }
+}
+
+/// Tells all players about the shutdown and closes their connections.
+async fn shutdown_universe(universe: &Universe) {
+    let config = &universe.config().shutdown;
+    universe
+        .begin_shutdown(&Message::ServerShutdown(ServerShutdownMessage {
+            reason: config.reason.clone(),
+            reconnect_after: config.reconnect_after_secs,
+        }))
+        .await;
 
-    let make_svc = make_service_fn(move |_| {
-        let universe = universe.clone();
-        let routes = warp::path("ws")
+    // the grace period lets commands that are still being processed
+    // finish and gives players the chance to read the message before they
+    // are disconnected.
+    time::delay_for(Duration::from_secs(config.grace_period_secs)).await;
+    persist_games(universe).await;
+
+    universe.close_connections(SHUTDOWN_CLOSE_REASON).await;
+    let started_at = Instant::now();
+    while universe.player_count().await > 0 && started_at.elapsed() < CLOSE_TIMEOUT {
+        time::delay_for(Duration::from_millis(50)).await;
+    }
+}
+
+/// Runs the server on the listener passed by `systemfd` or on port 8002.
+///
+/// The server shuts down gracefully on ctrl-c or `SIGTERM`.
+pub async fn serve(config: Config) {
+    let mut listenfd = listenfd::ListenFd::from_env();
+    let listener = match listenfd.take_tcp_listener(0).unwrap() {
+        Some(listener) => listener,
+        None => TcpListener::bind(("127.0.0.1", 8002)).unwrap(),
+    };
+    serve_listener(config, listener, shutdown_signal()).await;
+}
+
+/// Runs the server on an already bound listener until `shutdown` resolves.
+pub async fn serve_listener<F>(config: Config, listener: TcpListener, shutdown: F)
+where
+    F: Future<Output = ()>,
+{
+    let filter = BlocklistFilter::from_config(&config.moderation).expect("invalid blocklist");
+    let accounts = AccountStore::from_config(&config.accounts).expect("invalid account database");
+    let universe = Arc::new(Universe::new(config, Box::new(filter), accounts));
+    tokio::spawn(reaper::run(universe.clone()));
+
+    let service_universe = universe.clone();
+    let make_svc = make_service_fn(move |conn: &AddrStream| {
+        // warp only knows the remote address when it runs the server itself
+        let remote_addr = conn.remote_addr().ip();
+        let universe = service_universe.clone();
+        let admin = admin::routes(universe.clone());
+        let universe_filter = warp::any().map(move || universe.clone());
+        let ws = warp::path("ws")
             .and(warp::ws())
-            .and(warp::any().map(move || universe.clone()))
-            .map(|ws: warp::ws::Ws, universe: Arc<Universe>| {
-                ws.on_upgrade(move |ws| on_player_connected(universe, ws))
+            .and(universe_filter.clone())
+            .map(move |ws: warp::ws::Ws, universe: Arc<Universe>| {
+                ws.on_upgrade(move |ws| on_player_connected(universe, ws, remote_addr))
             });
+        let list_games = warp::path("api")
+            .and(warp::path("games"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(universe_filter.clone())
+            .and_then(on_http_list_games);
+        let leaderboard = warp::path("api")
+            .and(warp::path("leaderboard"))
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(universe_filter.clone())
+            .and_then(on_http_leaderboard);
+        let metrics = warp::path("metrics")
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(universe_filter)
+            .and_then(on_http_metrics);
+        let healthz = warp::path("healthz")
+            .and(warp::path::end())
+            .and(warp::get())
+            .map(|| "ok");
+        let routes = ws
+            .or(list_games)
+            .or(leaderboard)
+            .or(metrics)
+            .or(healthz)
+            .or(admin);
         let svc = warp::service(routes);
         async move { Ok::<_, Infallible>(svc) }
     });
 
-    let mut listenfd = listenfd::ListenFd::from_env();
-    let server = if let Some(l) = listenfd.take_tcp_listener(0).unwrap() {
-        Server::from_tcp(l).unwrap()
-    } else {
-        Server::bind(&([127, 0, 0, 1], 8002).into())
-    };
-    server.serve(make_svc).await.unwrap();
+    let (stop_accepting_tx, stop_accepting_rx) = oneshot::channel::<()>();
+    let server = Server::from_tcp(listener)
+        .unwrap()
+        .serve(make_svc)
+        .with_graceful_shutdown(async {
+            stop_accepting_rx.await.ok();
+        });
+    let server = tokio::spawn(server);
+
+    shutdown.await;
+    log::info!("shutting down");
+    stop_accepting_tx.send(()).ok();
+    shutdown_universe(&universe).await;
+    if let Ok(Err(err)) = server.await {
+        log::error!("server error: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invisible_chars() {
+        for &c in &[
+            '\n', '\u{7f}', '\u{ad}', '\u{34f}', '\u{115f}', '\u{1160}', '\u{180e}', '\u{200b}',
+            '\u{200d}', '\u{202e}', '\u{2060}', '\u{2066}', '\u{2069}', '\u{3164}', '\u{ffa0}',
+            '\u{feff}',
+        ] {
+            assert!(is_invisible_char(c), "{:?} should be invisible", c);
+        }
+        for &c in &['a', 'Z', ' ', 'ä', '한', '😀', '-'] {
+            assert!(!is_invisible_char(c), "{:?} should be visible", c);
+        }
+    }
 }
@@ -1,18 +1,39 @@
 use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::sync::{mpsc, RwLock};
 use uuid::Uuid;
 use warp::ws;
 
+use crate::accounts::AccountStore;
+use crate::config::Config;
 use crate::game::Game;
-use crate::protocol::{Message, PlayerInfo, ProtocolError, ProtocolErrorKind};
-use crate::utils::generate_join_code;
+use crate::metrics::{message_name, Metrics, UniverseStats};
+use crate::moderation::MessageFilter;
+use crate::protocol::{
+    Encoding, ErrorCode, GameListEntry, GameMode, Message, PlayerInfo, PlayerStats, ProtocolError,
+    SpectatorVisibility, PROTOCOL_VERSION,
+};
+use crate::utils::{generate_join_code, TokenBucket};
+
+/// Identifies where a player connected from.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerOrigin {
+    pub session_token: Option<String>,
+    pub remote_addr: Option<IpAddr>,
+}
 
 pub struct UniversePlayerState {
     player_info: PlayerInfo,
+    origin: PlayerOrigin,
+    protocol_version: Option<u32>,
+    encoding: Encoding,
     is_authenticated: bool,
     game_id: Option<Uuid>,
+    chat_bucket: TokenBucket,
     tx: mpsc::UnboundedSender<Result<ws::Message, warp::Error>>,
 }
 
@@ -24,21 +45,83 @@ pub struct UniverseState {
 
 pub struct Universe {
     state: Arc<RwLock<UniverseState>>,
+    config: Config,
+    filter: Box<dyn MessageFilter>,
+    accounts: Option<Arc<AccountStore>>,
+    metrics: Arc<Metrics>,
+    shutting_down: AtomicBool,
 }
 
 impl Universe {
-    pub fn new() -> Universe {
+    pub fn new(
+        config: Config,
+        filter: Box<dyn MessageFilter>,
+        accounts: Option<AccountStore>,
+    ) -> Universe {
         Universe {
             state: Arc::new(RwLock::new(UniverseState {
                 players: HashMap::new(),
                 games: HashMap::new(),
                 joinable_games: HashMap::new(),
             })),
+            config,
+            filter,
+            accounts: accounts.map(Arc::new),
+            metrics: Arc::new(Metrics::default()),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the server config.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns the filter for user supplied text.
+    pub fn filter(&self) -> &dyn MessageFilter {
+        &*self.filter
+    }
+
+    /// Returns the metrics of this server.
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    /// Returns the account store if accounts are enabled.
+    pub fn accounts(&self) -> Result<Arc<AccountStore>, ProtocolError> {
+        self.accounts.clone().ok_or_else(|| {
+            ProtocolError::new(
+                ErrorCode::AccountsDisabled,
+                "accounts are not enabled on this server",
+            )
+        })
+    }
+
+    /// Checks if the server supports accounts.
+    pub fn has_accounts(&self) -> bool {
+        self.accounts.is_some()
+    }
+
+    /// Adds the statistics of a finished game to the players' accounts.
+    pub async fn record_stats(&self, records: Vec<(String, PlayerStats)>) {
+        let accounts = match self.accounts {
+            Some(ref accounts) if !records.is_empty() => accounts.clone(),
+            _ => return,
+        };
+        // database errors are logged by the account store.
+        let result = tokio::task::spawn_blocking(move || accounts.record_stats(&records)).await;
+        if let Err(err) = result {
+            log::error!("failed to record stats: {}", err);
         }
     }
 
     /// Starts a new game.
-    pub async fn new_game(self: &Arc<Self>) -> Arc<Game> {
+    pub async fn new_game(
+        self: &Arc<Self>,
+        public: bool,
+        mode: GameMode,
+        spectator_visibility: SpectatorVisibility,
+    ) -> Arc<Game> {
         let mut universe_state = self.state.write().await;
 
         loop {
@@ -47,7 +130,13 @@ impl Universe {
                 continue;
             }
 
-            let game = Arc::new(Game::new(join_code, self.clone()));
+            let game = Arc::new(Game::new(
+                join_code,
+                public,
+                mode,
+                spectator_visibility,
+                self.clone(),
+            ));
             universe_state.games.insert(game.id(), game.clone());
             universe_state
                 .joinable_games
@@ -56,7 +145,35 @@ impl Universe {
         }
     }
 
+    /// Returns the lobby listing of all public games.
+    pub async fn list_public_games(&self) -> Vec<GameListEntry> {
+        // assign to temporary to release lock.
+        let games: Vec<Arc<Game>> = {
+            let universe_state = self.state.read().await;
+            universe_state
+                .joinable_games
+                .values()
+                .filter_map(|game_id| universe_state.games.get(game_id))
+                .filter(|game| game.is_public())
+                .cloned()
+                .collect()
+        };
+
+        let mut rv = Vec::with_capacity(games.len());
+        for game in games {
+            rv.push(game.list_entry().await);
+        }
+        rv.sort_by(|a, b| {
+            b.is_pregame
+                .cmp(&a.is_pregame)
+                .then_with(|| a.join_code.cmp(&b.join_code))
+        });
+        rv
+    }
+
     /// Joins a player into a game by join code.
+    ///
+    /// Players can join at any time but they always join as spectators.
     pub async fn join_game(
         &self,
         player_id: Uuid,
@@ -73,22 +190,15 @@ impl Universe {
 
         if let Some(game_id) = game_id {
             if let Some(game) = self.get_game(game_id).await {
-                if game.is_joinable().await {
-                    game.add_player(player_id).await;
-                    return Ok(game);
-                } else {
-                    return Err(ProtocolError::new(
-                        ProtocolErrorKind::InvalidCommand,
-                        "game is currently not joinable",
-                    ));
-                }
+                game.add_player(player_id).await;
+                return Ok(game);
             }
         }
 
-        Err(ProtocolError::new(
-            ProtocolErrorKind::NotFound,
-            "game does not exist",
-        ))
+        Err(
+            ProtocolError::new(ErrorCode::GameNotFound, "game does not exist")
+                .with_field("join_code"),
+        )
     }
 
     /// Registers a player.
@@ -98,6 +208,7 @@ impl Universe {
     pub async fn add_player(
         &self,
         tx: mpsc::UnboundedSender<Result<ws::Message, warp::Error>>,
+        remote_addr: Option<IpAddr>,
     ) -> Uuid {
         let player_id = Uuid::new_v4();
         let mut universe_state = self.state.write().await;
@@ -107,15 +218,45 @@ impl Universe {
                 player_info: PlayerInfo {
                     id: player_id,
                     nickname: "anonymous".into(),
+                    is_bot: false,
+                    account: None,
+                },
+                origin: PlayerOrigin {
+                    session_token: None,
+                    remote_addr,
                 },
                 game_id: None,
+                protocol_version: None,
+                encoding: Encoding::Json,
                 is_authenticated: false,
+                chat_bucket: TokenBucket::new(
+                    self.config.chat.rate_limit_burst,
+                    self.config.chat.rate_limit_per_second,
+                ),
                 tx,
             },
         );
         player_id
     }
 
+    /// Registers a bot.
+    ///
+    /// Bots are regular players but they are not connected through a
+    /// websocket.  Instead their messages are sent to the given channel.
+    pub async fn add_bot(
+        &self,
+        tx: mpsc::UnboundedSender<Result<ws::Message, warp::Error>>,
+    ) -> Uuid {
+        let player_id = self.add_player(tx, None).await;
+        let mut universe_state = self.state.write().await;
+        if let Some(state) = universe_state.players.get_mut(&player_id) {
+            state.player_info.is_bot = true;
+            // bots live in the server and always speak the current version.
+            state.protocol_version = Some(PROTOCOL_VERSION);
+        }
+        player_id
+    }
+
     /// Returns the player.
     pub async fn get_player_info(&self, player_id: Uuid) -> Option<PlayerInfo> {
         let universe_state = self.state.read().await;
@@ -125,6 +266,66 @@ impl Universe {
             .map(|x| x.player_info.clone())
     }
 
+    /// Returns the origins of the given players.
+    pub async fn get_player_origins<I>(&self, player_ids: I) -> HashMap<Uuid, PlayerOrigin>
+    where
+        I: IntoIterator<Item = Uuid>,
+    {
+        let universe_state = self.state.read().await;
+        player_ids
+            .into_iter()
+            .filter_map(|player_id| {
+                universe_state
+                    .players
+                    .get(&player_id)
+                    .map(|x| (player_id, x.origin.clone()))
+            })
+            .collect()
+    }
+
+    /// Records the protocol version and encoding the player negotiated.
+    pub async fn set_protocol_version(
+        &self,
+        player_id: Uuid,
+        protocol_version: u32,
+        encoding: Encoding,
+    ) -> Result<(), ProtocolError> {
+        let mut universe_state = self.state.write().await;
+        match universe_state.players.get_mut(&player_id) {
+            Some(state) if state.protocol_version.is_some() => Err(ProtocolError::new(
+                ErrorCode::AlreadySaidHello,
+                "cannot say hello twice",
+            )),
+            Some(state) => {
+                state.protocol_version = Some(protocol_version);
+                state.encoding = encoding;
+                Ok(())
+            }
+            None => Err(ProtocolError::new(
+                ErrorCode::PlayerNotFound,
+                "couldn't find user in state",
+            )),
+        }
+    }
+
+    /// Returns the protocol version of the player if they said hello.
+    pub async fn get_protocol_version(&self, player_id: Uuid) -> Option<u32> {
+        let universe_state = self.state.read().await;
+        universe_state
+            .players
+            .get(&player_id)
+            .and_then(|x| x.protocol_version)
+    }
+
+    /// Returns the encoding of messages sent to the player.
+    pub async fn get_encoding(&self, player_id: Uuid) -> Encoding {
+        let universe_state = self.state.read().await;
+        universe_state
+            .players
+            .get(&player_id)
+            .map_or(Encoding::Json, |x| x.encoding)
+    }
+
     /// Authenticates a player.
     ///
     /// If the user is already authenticated this returns `false`.
@@ -132,22 +333,26 @@ impl Universe {
         &self,
         player_id: Uuid,
         nickname: String,
+        session_token: Option<String>,
+        account: Option<String>,
     ) -> Result<PlayerInfo, ProtocolError> {
         let mut universe_state = self.state.write().await;
         if let Some(player_state) = universe_state.players.get_mut(&player_id) {
             if player_state.is_authenticated {
                 Err(ProtocolError::new(
-                    ProtocolErrorKind::AlreadyAuthenticated,
+                    ErrorCode::AlreadyAuthenticated,
                     "cannot authenticate twice",
                 ))
             } else {
                 player_state.is_authenticated = true;
                 player_state.player_info.nickname = nickname;
+                player_state.player_info.account = account;
+                player_state.origin.session_token = session_token;
                 Ok(player_state.player_info.clone())
             }
         } else {
             Err(ProtocolError::new(
-                ProtocolErrorKind::InternalError,
+                ErrorCode::PlayerNotFound,
                 "couldn't find user in state",
             ))
         }
@@ -163,6 +368,23 @@ impl Universe {
         }
     }
 
+    /// Takes a token out of the player's chat rate limit.
+    ///
+    /// If the player is sending messages too quickly this returns how long
+    /// they have to wait.
+    pub async fn take_chat_token(&self, player_id: Uuid) -> Result<(), Duration> {
+        let mut universe_state = self.state.write().await;
+        if let Some(state) = universe_state.players.get_mut(&player_id) {
+            if state.chat_bucket.try_take() {
+                Ok(())
+            } else {
+                Err(state.chat_bucket.time_until_available())
+            }
+        } else {
+            Err(Duration::from_secs(0))
+        }
+    }
+
     /// Unregisters a player.
     pub async fn remove_player(&self, player_id: Uuid) {
         let mut universe_state = self.state.write().await;
@@ -180,6 +402,97 @@ impl Universe {
         }
     }
 
+    /// Returns all games.
+    pub async fn get_games(&self) -> Vec<Arc<Game>> {
+        let universe_state = self.state.read().await;
+        universe_state.games.values().cloned().collect()
+    }
+
+    /// Collects the values for the metrics that are not counters.
+    pub async fn stats(&self) -> UniverseStats {
+        let authenticated_players = {
+            let universe_state = self.state.read().await;
+            universe_state
+                .players
+                .values()
+                .filter(|x| x.is_authenticated)
+                .count()
+        };
+        let mut games = vec![];
+        for game in self.get_games().await {
+            games.push(game.turn().await);
+        }
+        UniverseStats {
+            authenticated_players,
+            games,
+        }
+    }
+
+    /// Returns a game by its join code.
+    pub async fn get_game_by_join_code(&self, join_code: &str) -> Option<Arc<Game>> {
+        let universe_state = self.state.read().await;
+        universe_state
+            .joinable_games
+            .get(join_code)
+            .and_then(|game_id| universe_state.games.get(game_id))
+            .cloned()
+    }
+
+    /// Pings every connected player.
+    ///
+    /// Clients answer with a pong which keeps their connection alive.
+    pub async fn ping_all(&self) {
+        let universe_state = self.state.read().await;
+        for state in universe_state.players.values() {
+            state.tx.send(Ok(ws::Message::ping(Vec::new()))).ok();
+        }
+    }
+
+    /// Sends a message to every connected player.
+    pub async fn broadcast(&self, message: &Message) {
+        let player_ids: Vec<Uuid> = self.state.read().await.players.keys().copied().collect();
+        for player_id in player_ids {
+            self.send(player_id, message).await;
+        }
+    }
+
+    /// Tells all players that the server shuts down.
+    ///
+    /// From here on new connections are refused.
+    pub async fn begin_shutdown(&self, message: &Message) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        self.broadcast(message).await;
+    }
+
+    /// Returns `true` once the server started shutting down.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Closes the websockets of all players with a close frame.
+    ///
+    /// Bots do not have a websocket and are removed right away.  Players
+    /// are removed once their client finished the close handshake.
+    pub async fn close_connections(&self, reason: &'static str) {
+        let mut universe_state = self.state.write().await;
+        universe_state.players.retain(|_, state| {
+            if state.player_info.is_bot {
+                return false;
+            }
+            // 1001 is "going away"
+            state
+                .tx
+                .send(Ok(ws::Message::close_with(1001u16, reason)))
+                .ok();
+            true
+        });
+    }
+
+    /// Returns the number of connected players including bots.
+    pub async fn player_count(&self) -> usize {
+        self.state.read().await.players.len()
+    }
+
     /// Returns a game by ID
     pub async fn get_game(&self, game_id: Uuid) -> Option<Arc<Game>> {
         let universe_state = self.state.read().await;
@@ -189,7 +502,13 @@ impl Universe {
     /// Removes a game from the universe.
     pub async fn remove_game(&self, game_id: Uuid) -> bool {
         let mut universe_state = self.state.write().await;
-        universe_state.games.remove(&game_id).is_some()
+        match universe_state.games.remove(&game_id) {
+            Some(game) => {
+                universe_state.joinable_games.remove(game.join_code());
+                true
+            }
+            None => false,
+        }
     }
 
     /// Returns the game a player is in.
@@ -214,8 +533,29 @@ impl Universe {
     pub async fn send(&self, player_id: Uuid, message: &Message) {
         let universe_state = self.state.write().await;
         if let Some(ref state) = universe_state.players.get(&player_id) {
-            let s = serde_json::to_string(message).unwrap();
-            if let Err(_disconnected) = state.tx.send(Ok(ws::Message::text(s))) {
+            let started_at = Instant::now();
+            let msg = match state.encoding {
+                Encoding::Json => serde_json::to_string(message)
+                    .map(ws::Message::text)
+                    .map_err(|err| err.to_string()),
+                Encoding::Msgpack => rmp_serde::to_vec_named(message)
+                    .map(ws::Message::binary)
+                    .map_err(|err| err.to_string()),
+            };
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(err) => {
+                    log::error!(
+                        "could not encode {} message for player {}: {}",
+                        message_name(message),
+                        player_id,
+                        err
+                    );
+                    return;
+                }
+            };
+            self.metrics.message_sent(message, started_at.elapsed());
+            if let Err(_disconnected) = state.tx.send(Ok(msg)) {
                 // The tx is disconnected, our `user_disconnected` code
                 // should be happening in another task, nothing more to
                 // do here.
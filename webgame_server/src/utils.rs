@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use rand::{seq::SliceRandom, thread_rng};
 
 const CHARS: &[u8; 22] = b"BCDFGHJKLMNPQRSTUVWXZY";
@@ -8,3 +10,88 @@ pub fn generate_join_code() -> String {
         .map(|_| *CHARS.choose(&mut rng).unwrap() as char)
         .collect()
 }
+
+/// A simple token bucket used for rate limiting.
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(capacity: u32, refill_per_second: f64) -> TokenBucket {
+        TokenBucket {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_second,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes a token out of the bucket.
+    ///
+    /// Returns `false` if the bucket is currently empty.
+    pub fn try_take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns how long it takes until the next token is available.
+    pub fn time_until_available(&self) -> Duration {
+        if self.tokens >= 1.0 || self.refill_per_second <= 0.0 {
+            return Duration::from_secs(0);
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn test_token_bucket_burst() {
+        let mut bucket = TokenBucket::new(3, 0.0);
+        assert!(bucket.try_take());
+        assert!(bucket.try_take());
+        assert!(bucket.try_take());
+        assert!(!bucket.try_take());
+        assert!(!bucket.try_take());
+    }
+
+    #[test]
+    fn test_token_bucket_refill() {
+        let mut bucket = TokenBucket::new(1, 1.0);
+        assert!(bucket.try_take());
+        assert!(!bucket.try_take());
+        let wait = bucket.time_until_available();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+
+        let mut bucket = TokenBucket::new(2, 1000.0);
+        assert!(bucket.try_take());
+        assert!(bucket.try_take());
+        thread::sleep(Duration::from_millis(10));
+        assert!(bucket.try_take());
+    }
+
+    #[test]
+    fn test_token_bucket_capacity() {
+        let mut bucket = TokenBucket::new(2, 1000.0);
+        thread::sleep(Duration::from_millis(10));
+        assert!(bucket.try_take());
+        assert!(bucket.try_take());
+        assert!(!bucket.try_take());
+    }
+}
@@ -0,0 +1,1475 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use futures::future;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+use webgame_protocol::{
+    AddBotCommand, Character, ChatChannel, Clue, Command, Credentials, Encoding, ErrorCode,
+    GameInfo, GameListEntry, GameMode, GameStateSnapshot, JoinGameCommand, LeaderboardEntry,
+    Message, NewGameCommand, PlayerRole, PlayerStats, ProtocolErrorKind, RegisterCommand,
+    RequestMagicTokenCommand, RevealCardCommand, SendTextCommand, SetPlayerTeamCommand,
+    ShareCodenameCommand, SpectatorVisibility, Team, Tile, Turn, PROTOCOL_VERSION,
+};
+use webgame_server::config::Config;
+use webgame_server::server::serve_listener;
+use webgame_test_client::{Client, Error};
+
+/// Starts a server on an ephemeral port and returns its websocket url.
+fn start_server() -> String {
+    start_server_with_config(Config::default())
+}
+
+fn start_server_with_config(config: Config) -> String {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve_listener(config, listener, future::pending()));
+    format!("ws://{}/ws", addr)
+}
+
+async fn connect(url: &str, nickname: &str) -> Client {
+    let mut client = Client::connect(url).await.unwrap();
+    client.authenticate(nickname).await.unwrap();
+    client
+}
+
+fn expect_error(result: Result<GameStateSnapshot, Error>, kind: ProtocolErrorKind) {
+    match result {
+        Err(Error::Protocol(err)) => assert_eq!(err.kind(), kind, "{}", err.message()),
+        other => panic!("expected {:?} error, got {:?}", kind, other),
+    }
+}
+
+fn expect_code<T: fmt::Debug>(result: Result<T, Error>, code: ErrorCode) {
+    match result {
+        Err(Error::Protocol(err)) => assert_eq!(err.code(), code, "{}", err.message()),
+        other => panic!("expected {:?} error, got {:?}", code, other),
+    }
+}
+
+/// Joins a team and waits until the server assigned the expected role.
+async fn take_seat(client: &mut Client, nickname: &str, team: Team, role: PlayerRole) {
+    client
+        .send(Command::SetPlayerTeam(SetPlayerTeamCommand {
+            team: Some(team),
+        }))
+        .await
+        .unwrap();
+    client
+        .wait_for_state(|state| {
+            state
+                .players
+                .iter()
+                .any(|x| x.player.nickname == nickname && x.team == Some(team) && x.role == role)
+        })
+        .await
+        .unwrap();
+}
+
+fn find_tile(key: &[Tile], character: Character) -> usize {
+    key.iter()
+        .position(|tile| !tile.spotted && tile.character == character)
+        .unwrap()
+}
+
+/// Starts a game with two seated players per team.
+///
+/// The clients are returned as red spymaster, red operative, blue
+/// spymaster and blue operative together with the key card.
+async fn start_game(url: &str, cmd: NewGameCommand) -> (GameInfo, Vec<Client>, Vec<Tile>) {
+    let seats = [
+        ("Red Spymaster", Team::Red, PlayerRole::Spymaster),
+        ("Red Operative", Team::Red, PlayerRole::Operative),
+        ("Blue Spymaster", Team::Blue, PlayerRole::Spymaster),
+        ("Blue Operative", Team::Blue, PlayerRole::Operative),
+    ];
+    let mut clients = vec![];
+    let mut game_info = None;
+    for &(nickname, team, role) in seats.iter() {
+        let mut client = connect(url, nickname).await;
+        match game_info {
+            None => game_info = Some(client.new_game(cmd.clone()).await.unwrap()),
+            Some(ref game_info) => {
+                client.join_game(&game_info.join_code).await.unwrap();
+            }
+        }
+        take_seat(&mut client, nickname, team, role).await;
+        clients.push(client);
+    }
+    for client in clients.iter_mut() {
+        client.send(Command::MarkReady).await.unwrap();
+    }
+    let key = clients[0]
+        .wait_for_state(|state| state.turn != Turn::Pregame)
+        .await
+        .unwrap()
+        .tiles;
+    (game_info.unwrap(), clients, key)
+}
+
+#[tokio::test]
+async fn test_authenticate() {
+    let url = start_server();
+    let mut client = Client::connect(&url).await.unwrap();
+
+    client.send(Command::ListGames).await.unwrap();
+    expect_error(
+        client.wait_for_state(|_| true).await,
+        ProtocolErrorKind::NotAuthenticated,
+    );
+
+    let player_info = client.authenticate("  Alice ").await.unwrap();
+    assert_eq!(player_info.nickname, "Alice");
+    assert!(!player_info.is_bot);
+
+    match client.authenticate("Alice").await {
+        Err(Error::Protocol(err)) => {
+            assert_eq!(err.kind(), ProtocolErrorKind::AlreadyAuthenticated)
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_hello() {
+    let url = start_server();
+
+    let mut client = Client::connect_without_hello(&url).await.unwrap();
+    match client.authenticate("Alice").await {
+        Err(Error::Protocol(err)) => {
+            assert_eq!(err.kind(), ProtocolErrorKind::UnsupportedVersion);
+            assert_eq!(err.code(), ErrorCode::HelloRequired);
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    match client.hello(PROTOCOL_VERSION + 1, Encoding::Json).await {
+        Err(Error::Protocol(err)) => {
+            assert_eq!(err.kind(), ProtocolErrorKind::UnsupportedVersion);
+            assert_eq!(err.code(), ErrorCode::UnsupportedVersion);
+            assert_eq!(
+                err.details().unwrap().supported_versions,
+                vec![PROTOCOL_VERSION]
+            );
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let hello = client
+        .hello(PROTOCOL_VERSION, Encoding::Json)
+        .await
+        .unwrap();
+    assert_eq!(hello.protocol_version, PROTOCOL_VERSION);
+    assert!(hello.supported_versions.contains(&PROTOCOL_VERSION));
+    assert!(hello.features.iter().any(|x| x == "bots"));
+    match client.hello(PROTOCOL_VERSION, Encoding::Json).await {
+        Err(Error::Protocol(err)) => assert_eq!(err.kind(), ProtocolErrorKind::BadState),
+        other => panic!("unexpected result {:?}", other),
+    }
+    client.authenticate("Alice").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_ids() {
+    let url = start_server();
+    let mut client = connect(&url, "Alice").await;
+
+    client.request(Command::ListGames).await.unwrap();
+    match client
+        .request(Command::JoinGame(JoinGameCommand {
+            join_code: "XXXXXX".into(),
+        }))
+        .await
+    {
+        Err(Error::Protocol(err)) => {
+            assert_eq!(err.kind(), ProtocolErrorKind::NotFound);
+            assert_eq!(err.request_id(), Some(2));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+    match client.request(Command::EndTurn).await {
+        Err(Error::Protocol(err)) => assert_eq!(err.request_id(), Some(3)),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    // commands without a request ID are not acknowledged.
+    client.send(Command::ListGames).await.unwrap();
+    client.request(Command::ListGames).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_msgpack() {
+    let url = start_server();
+
+    let mut client = Client::connect_with_encoding(&url, Encoding::Msgpack)
+        .await
+        .unwrap();
+    assert_eq!(client.encoding(), Encoding::Msgpack);
+    let player_info = client.authenticate("Alice").await.unwrap();
+    client.new_game(NewGameCommand::default()).await.unwrap();
+    let state = client.wait_for_state(|_| true).await.unwrap();
+    assert_eq!(state.players[0].player.id, player_info.id);
+    assert_eq!(state.turn, Turn::Pregame);
+
+    // requests are acknowledged in msgpack too.
+    client.request(Command::ListGames).await.unwrap();
+    expect_code(
+        client
+            .request(Command::JoinGame(JoinGameCommand {
+                join_code: "XXXXXX".into(),
+            }))
+            .await,
+        ErrorCode::GameNotFound,
+    );
+
+    // clients that do not ask for msgpack get json.
+    let client = Client::connect(&url).await.unwrap();
+    assert_eq!(client.encoding(), Encoding::Json);
+}
+
+#[tokio::test]
+async fn test_idle_games_are_closed() {
+    let mut config = Config::default();
+    config.timeouts.game_idle_secs = 2;
+    config.timeouts.heartbeat_interval_secs = 1;
+    let url = start_server_with_config(config);
+
+    let mut idle = connect(&url, "Idle").await;
+    idle.new_game(NewGameCommand::default()).await.unwrap();
+    let mut active = connect(&url, "Active").await;
+    active.new_game(NewGameCommand::default()).await.unwrap();
+
+    // any command in the game counts as activity
+    for _ in 0..8 {
+        active.request(Command::ListGames).await.unwrap();
+        tokio::time::delay_for(Duration::from_millis(500)).await;
+    }
+
+    idle.wait_for(|msg| match msg {
+        Message::ServerNotice(_) => Some(()),
+        _ => None,
+    })
+    .await
+    .unwrap();
+    idle.wait_for(|msg| match msg {
+        Message::GameLeft => Some(()),
+        _ => None,
+    })
+    .await
+    .unwrap();
+
+    active
+        .request(Command::SendText(SendTextCommand {
+            text: "still here".into(),
+            channel: ChatChannel::All,
+        }))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    let mut config = Config::default();
+    config.shutdown.grace_period_secs = 0;
+    config.shutdown.reconnect_after_secs = Some(30);
+    let persist_dir = env::temp_dir().join(format!("webgame-games-{}", Uuid::new_v4()));
+    config.shutdown.persist_dir = Some(persist_dir.clone());
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_listener(config, listener, async {
+        shutdown_rx.await.ok();
+    }));
+
+    let mut client = connect(&url, "Alice").await;
+    let game_info = client.new_game(NewGameCommand::default()).await.unwrap();
+    shutdown_tx.send(()).unwrap();
+    let msg = client
+        .wait_for(|msg| match msg {
+            Message::ServerShutdown(msg) => Some(msg),
+            _ => None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(msg.reconnect_after, Some(30));
+    match client.recv().await {
+        Err(Error::Closed) => {}
+        other => panic!("expected the connection to close, got {:?}", other),
+    }
+    drop(client);
+
+    server.await.unwrap();
+    assert!(Client::connect(&url).await.is_err());
+
+    let path = persist_dir.join(format!("{}.json", game_info.join_code));
+    let game: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!(game["game_id"], game_info.game_id.to_string());
+    assert_eq!(game["state"]["tiles"].as_array().unwrap().len(), 25);
+    assert_eq!(game["state"]["players"][0]["player"]["nickname"], "Alice");
+    fs::remove_dir_all(&persist_dir).ok();
+}
+
+#[tokio::test]
+async fn test_join_unknown_game() {
+    let url = start_server();
+    let mut client = connect(&url, "Alice").await;
+    match client.join_game("XXXXXX").await {
+        Err(Error::Protocol(err)) => {
+            assert_eq!(err.kind(), ProtocolErrorKind::NotFound);
+            assert_eq!(err.code(), ErrorCode::GameNotFound);
+            assert_eq!(
+                err.details().and_then(|x| x.field.as_deref()),
+                Some("join_code")
+            );
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_bots_wait_for_humans() {
+    let url = start_server();
+    let mut alice = connect(&url, "Alice").await;
+    alice.new_game(NewGameCommand::default()).await.unwrap();
+    take_seat(&mut alice, "Alice", Team::Red, PlayerRole::Spymaster).await;
+    for &(team, role) in &[
+        (Team::Red, PlayerRole::Operative),
+        (Team::Blue, PlayerRole::Spymaster),
+        (Team::Blue, PlayerRole::Operative),
+    ] {
+        alice
+            .request(Command::AddBot(AddBotCommand {
+                team,
+                role,
+                difficulty: Default::default(),
+            }))
+            .await
+            .unwrap();
+    }
+
+    let state = alice
+        .wait_for_state(|state| state.players.iter().filter(|x| x.ready).count() == 3)
+        .await
+        .unwrap();
+    assert_eq!(state.turn, Turn::Pregame);
+
+    alice.send(Command::MarkReady).await.unwrap();
+    alice
+        .wait_for_state(|state| state.turn != Turn::Pregame)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_game_list() {
+    let url = start_server();
+    let mut alice = connect(&url, "Alice").await;
+    let public_game = alice
+        .new_game(NewGameCommand {
+            public: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let mut bob = connect(&url, "Bob").await;
+    bob.new_game(NewGameCommand::default()).await.unwrap();
+
+    // a public game that already started is listed after the others
+    let mut dave = connect(&url, "Dave").await;
+    let started_game = dave
+        .new_game(NewGameCommand {
+            public: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    take_seat(&mut dave, "Dave", Team::Red, PlayerRole::Spymaster).await;
+    for &(team, role) in &[
+        (Team::Red, PlayerRole::Operative),
+        (Team::Blue, PlayerRole::Spymaster),
+        (Team::Blue, PlayerRole::Operative),
+    ] {
+        dave.request(Command::AddBot(AddBotCommand {
+            team,
+            role,
+            difficulty: Default::default(),
+        }))
+        .await
+        .unwrap();
+    }
+    dave.send(Command::MarkReady).await.unwrap();
+    dave.wait_for_state(|state| state.turn != Turn::Pregame)
+        .await
+        .unwrap();
+
+    let mut carol = connect(&url, "Carol").await;
+    carol.send(Command::ListGames).await.unwrap();
+    let games = carol
+        .wait_for(|msg| match msg {
+            Message::GameList(msg) => Some(msg.games),
+            _ => None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        games,
+        vec![
+            GameListEntry {
+                join_code: public_game.join_code.clone(),
+                host_nickname: "Alice".into(),
+                player_count: 1,
+                mode: GameMode::Classic,
+                is_pregame: true,
+            },
+            GameListEntry {
+                join_code: started_game.join_code.clone(),
+                host_nickname: "Dave".into(),
+                player_count: 4,
+                mode: GameMode::Classic,
+                is_pregame: false,
+            },
+        ]
+    );
+
+    let (status, body) = http_request(&url, "GET", "/api/games", "", "").await;
+    assert_eq!(status, 200, "{}", body);
+    let http_games: Vec<GameListEntry> = serde_json::from_str(&body).unwrap();
+    assert_eq!(http_games, games);
+}
+
+#[tokio::test]
+async fn test_duplicate_nicknames() {
+    let url = start_server();
+    let nickname = "Sixteen Chars!!!";
+    let mut alice = connect(&url, nickname).await;
+    let game_info = alice.new_game(NewGameCommand::default()).await.unwrap();
+    let mut bob = connect(&url, nickname).await;
+    bob.join_game(&game_info.join_code).await.unwrap();
+
+    let state = bob
+        .wait_for_state(|state| state.players.len() == 2)
+        .await
+        .unwrap();
+    let mut nicknames: Vec<_> = state.players.iter().map(|x| &x.player.nickname).collect();
+    nicknames.sort();
+    assert_eq!(nicknames, vec!["Sixteen Char (2)", nickname]);
+}
+
+#[tokio::test]
+async fn test_chat_rate_limit() {
+    let url = start_server();
+    let mut client = connect(&url, "Alice").await;
+    client.new_game(NewGameCommand::default()).await.unwrap();
+
+    let send_text = |channel| {
+        Command::SendText(SendTextCommand {
+            text: "hello".into(),
+            channel,
+        })
+    };
+
+    // messages on channels the player cannot use do not count
+    for _ in 0..10 {
+        match client.request(send_text(ChatChannel::Team)).await {
+            Err(Error::Protocol(err)) => assert_eq!(err.code(), ErrorCode::NotInTeam),
+            other => panic!("expected to be rejected, got {:?}", other),
+        }
+    }
+    client.request(send_text(ChatChannel::All)).await.unwrap();
+
+    let mut rejected = None;
+    for _ in 0..10 {
+        if let Err(err) = client.request(send_text(ChatChannel::All)).await {
+            rejected = Some(err);
+            break;
+        }
+    }
+    match rejected {
+        Some(Error::Protocol(err)) => {
+            assert_eq!(err.code(), ErrorCode::RateLimited);
+            assert!(err.details().unwrap().retry_after_ms.unwrap() > 0);
+        }
+        other => panic!("expected to be rate limited, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_full_game() {
+    let url = start_server();
+
+    let mut red_spymaster = connect(&url, "Red Spymaster").await;
+    let game_info = red_spymaster
+        .new_game(NewGameCommand::default())
+        .await
+        .unwrap();
+    let mut red_operative = connect(&url, "Red Operative").await;
+    let mut blue_spymaster = connect(&url, "Blue Spymaster").await;
+    let mut blue_operative = connect(&url, "Blue Operative").await;
+    let mut spectator = connect(&url, "Spectator").await;
+    for client in [
+        &mut red_operative,
+        &mut blue_spymaster,
+        &mut blue_operative,
+        &mut spectator,
+    ]
+    .iter_mut()
+    {
+        let joined = client.join_game(&game_info.join_code).await.unwrap();
+        assert_eq!(joined.game_id, game_info.game_id);
+    }
+
+    // the first player of a team becomes its spymaster.
+    take_seat(
+        &mut red_spymaster,
+        "Red Spymaster",
+        Team::Red,
+        PlayerRole::Spymaster,
+    )
+    .await;
+    take_seat(
+        &mut red_operative,
+        "Red Operative",
+        Team::Red,
+        PlayerRole::Operative,
+    )
+    .await;
+    take_seat(
+        &mut blue_spymaster,
+        "Blue Spymaster",
+        Team::Blue,
+        PlayerRole::Spymaster,
+    )
+    .await;
+    take_seat(
+        &mut blue_operative,
+        "Blue Operative",
+        Team::Blue,
+        PlayerRole::Operative,
+    )
+    .await;
+
+    // nobody sees the key card before the game started.
+    let state = spectator.wait_for_state(|_| true).await.unwrap();
+    assert_eq!(state.turn, Turn::Pregame);
+    assert!(state
+        .tiles
+        .iter()
+        .all(|x| x.character == Character::Unknown));
+
+    for client in [
+        &mut red_spymaster,
+        &mut red_operative,
+        &mut blue_spymaster,
+        &mut blue_operative,
+    ]
+    .iter_mut()
+    {
+        client.send(Command::MarkReady).await.unwrap();
+    }
+
+    let is_started = |state: &GameStateSnapshot| state.turn != Turn::Pregame;
+    let key = red_spymaster
+        .wait_for_state(is_started)
+        .await
+        .unwrap()
+        .tiles;
+    assert!(key.iter().all(|x| x.character != Character::Unknown));
+    let state = blue_spymaster.wait_for_state(is_started).await.unwrap();
+    assert_eq!(state.tiles, key);
+    let state = spectator.wait_for_state(is_started).await.unwrap();
+    assert_eq!(state.tiles, key);
+    for client in [&mut red_operative, &mut blue_operative].iter_mut() {
+        let state = client.wait_for_state(is_started).await.unwrap();
+        assert!(state
+            .tiles
+            .iter()
+            .all(|x| x.character == Character::Unknown));
+        assert!(state.clue.is_none());
+    }
+
+    let starting_team = state.turn.team().unwrap();
+    let (mut first_spymaster, mut first_operative, mut second_spymaster, mut second_operative) =
+        match starting_team {
+            Team::Red => (red_spymaster, red_operative, blue_spymaster, blue_operative),
+            Team::Blue => (blue_spymaster, blue_operative, red_spymaster, red_operative),
+        };
+
+    // operatives cannot guess before their spymaster shared a codename.
+    first_operative
+        .send(Command::RevealCard(RevealCardCommand { index: 0 }))
+        .await
+        .unwrap();
+    expect_error(
+        first_operative.wait_for_state(|_| false).await,
+        ProtocolErrorKind::BadState,
+    );
+
+    // a new round can only be started once the game is over.
+    expect_code(
+        first_spymaster.request(Command::Rematch).await,
+        ErrorCode::GameNotOver,
+    );
+
+    first_spymaster
+        .send(Command::ShareCodename(ShareCodenameCommand {
+            codename: "Zyzzyva".into(),
+            number: 1,
+            unlimited: false,
+        }))
+        .await
+        .unwrap();
+    let state = first_operative
+        .wait_for_state(|state| state.clue.is_some())
+        .await
+        .unwrap();
+    assert_eq!(state.turn.team(), Some(starting_team));
+    assert_eq!(state.turn.role(), Some(PlayerRole::Operative));
+    assert_eq!(
+        state.clue,
+        Some(Clue {
+            team: starting_team,
+            word: "zyzzyva".into(),
+            number: 1,
+            unlimited: false,
+            guesses: 0,
+        })
+    );
+
+    // spymasters cannot reveal cards themselves.
+    first_spymaster
+        .send(Command::RevealCard(RevealCardCommand { index: 0 }))
+        .await
+        .unwrap();
+    expect_error(
+        first_spymaster.wait_for_state(|_| false).await,
+        ProtocolErrorKind::BadState,
+    );
+
+    // guessing an own agent lets the operatives continue.
+    let agent = find_tile(&key, starting_team.agent());
+    first_operative
+        .send(Command::RevealCard(RevealCardCommand { index: agent }))
+        .await
+        .unwrap();
+    let state = first_operative
+        .wait_for_state(|state| state.tiles[agent].spotted)
+        .await
+        .unwrap();
+    assert_eq!(state.tiles[agent].character, starting_team.agent());
+    assert_eq!(state.clue.as_ref().map(|x| x.guesses), Some(1));
+    assert_eq!(state.turn.role(), Some(PlayerRole::Operative));
+    assert_eq!(
+        state
+            .tiles
+            .iter()
+            .filter(|x| x.character != Character::Unknown)
+            .count(),
+        1
+    );
+
+    first_operative.send(Command::EndTurn).await.unwrap();
+    let state = second_spymaster
+        .wait_for_state(|state| state.turn.team() == Some(starting_team.other()))
+        .await
+        .unwrap();
+    assert_eq!(state.turn.role(), Some(PlayerRole::Spymaster));
+    assert!(state.clue.is_none());
+
+    // the number is dropped for unlimited guesses.
+    second_spymaster
+        .send(Command::ShareCodename(ShareCodenameCommand {
+            codename: "xylophonist".into(),
+            number: 2,
+            unlimited: true,
+        }))
+        .await
+        .unwrap();
+    second_operative
+        .wait_for_state(|state| state.clue.is_some())
+        .await
+        .unwrap();
+
+    // revealing the assassin loses the game.
+    let assassin = find_tile(&key, Character::Assassin);
+    second_operative
+        .send(Command::RevealCard(RevealCardCommand { index: assassin }))
+        .await
+        .unwrap();
+    let is_over = |state: &GameStateSnapshot| state.turn == Turn::Endgame;
+    let state = second_operative.wait_for_state(is_over).await.unwrap();
+    assert_eq!(state.winner, Some(starting_team));
+    assert_eq!(state.tiles[assassin].character, Character::Assassin);
+    // operatives get to see the key card after the game.
+    let characters = |tiles: &[Tile]| tiles.iter().map(|x| x.character).collect::<Vec<_>>();
+    assert_eq!(characters(&state.tiles), characters(&key));
+    let state = spectator.wait_for_state(is_over).await.unwrap();
+    assert_eq!(state.winner, Some(starting_team));
+    assert!(state.tiles[assassin].spotted);
+
+    // the clue log keeps every clue with the tiles guessed for it.
+    let clues: Vec<_> = state
+        .clues
+        .iter()
+        .map(|clue| {
+            let guesses: Vec<_> = clue
+                .guesses
+                .iter()
+                .map(|x| (x.index, x.character))
+                .collect();
+            (
+                clue.team,
+                clue.word.as_str(),
+                clue.number,
+                clue.unlimited,
+                guesses,
+            )
+        })
+        .collect();
+    assert_eq!(
+        clues,
+        vec![
+            (
+                starting_team,
+                "zyzzyva",
+                1,
+                false,
+                vec![(agent, starting_team.agent())]
+            ),
+            (
+                starting_team.other(),
+                "xylophonist",
+                0,
+                true,
+                vec![(assassin, Character::Assassin)]
+            ),
+        ]
+    );
+
+    // a rematch deals a new board and keeps the teams.
+    second_operative.request(Command::Rematch).await.unwrap();
+    let state = spectator
+        .wait_for_state(|state| state.turn == Turn::Pregame)
+        .await
+        .unwrap();
+    assert_eq!(state.winner, None);
+    assert!(state.clues.is_empty());
+    assert!(state.tiles.iter().all(|x| !x.spotted));
+    assert!(state
+        .players
+        .iter()
+        .all(|x| !x.ready && (x.player.nickname == "Spectator") == x.team.is_none()));
+}
+
+#[tokio::test]
+async fn test_join_started_game() {
+    let url = start_server();
+    let (game_info, mut clients, key) = start_game(&url, NewGameCommand::default()).await;
+
+    let red_operative = &mut clients[1];
+    red_operative
+        .request(Command::SendText(SendTextCommand {
+            text: "secret plan".into(),
+            channel: ChatChannel::Team,
+        }))
+        .await
+        .unwrap();
+    red_operative
+        .request(Command::SendText(SendTextCommand {
+            text: "good luck".into(),
+            channel: ChatChannel::All,
+        }))
+        .await
+        .unwrap();
+
+    // late joiners watch the game as spectators.
+    let mut spectator = connect(&url, "Spectator").await;
+    spectator.join_game(&game_info.join_code).await.unwrap();
+    let state = spectator.wait_for_state(|_| true).await.unwrap();
+    let me = state
+        .players
+        .iter()
+        .find(|x| x.player.nickname == "Spectator")
+        .unwrap();
+    assert_eq!(me.role, PlayerRole::Spectator);
+    assert_eq!(me.team, None);
+    assert_eq!(state.tiles, key);
+
+    // the chat history is replayed without the other teams' messages.
+    let text = spectator
+        .wait_for(|msg| match msg {
+            Message::Chat(chat) => Some(chat.text),
+            _ => None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(text, "good luck");
+
+    expect_code(
+        spectator
+            .request(Command::SetPlayerTeam(SetPlayerTeamCommand {
+                team: Some(Team::Red),
+            }))
+            .await,
+        ErrorCode::GameAlreadyStarted,
+    );
+    spectator
+        .request(Command::SendText(SendTextCommand {
+            text: "hi".into(),
+            channel: ChatChannel::Spectators,
+        }))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_spectator_visibility() {
+    let url = start_server();
+    for &visibility in [
+        SpectatorVisibility::RevealedOnly,
+        SpectatorVisibility::Delayed,
+    ]
+    .iter()
+    {
+        let cmd = NewGameCommand {
+            spectator_visibility: visibility,
+            ..NewGameCommand::default()
+        };
+        let (game_info, mut clients, key) = start_game(&url, cmd).await;
+        let mut spectator = connect(&url, "Spectator").await;
+        spectator.join_game(&game_info.join_code).await.unwrap();
+        let state = spectator.wait_for_state(|_| true).await.unwrap();
+        assert!(state
+            .tiles
+            .iter()
+            .all(|x| x.character == Character::Unknown));
+
+        let (spymaster, operative) = match state.turn.team().unwrap() {
+            Team::Red => (0, 1),
+            Team::Blue => (2, 3),
+        };
+        clients[spymaster]
+            .send(Command::ShareCodename(ShareCodenameCommand {
+                codename: "Zyzzyva".into(),
+                number: 1,
+                unlimited: false,
+            }))
+            .await
+            .unwrap();
+        clients[operative]
+            .wait_for_state(|state| state.clue.is_some())
+            .await
+            .unwrap();
+        let assassin = find_tile(&key, Character::Assassin);
+        clients[operative]
+            .send(Command::RevealCard(RevealCardCommand { index: assassin }))
+            .await
+            .unwrap();
+
+        // only delayed visibility shows the key card after the game.
+        let state = spectator
+            .wait_for_state(|state| state.turn == Turn::Endgame)
+            .await
+            .unwrap();
+        let known: Vec<_> = state
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.character != Character::Unknown)
+            .map(|(index, _)| index)
+            .collect();
+        match visibility {
+            SpectatorVisibility::Delayed => assert_eq!(known.len(), key.len()),
+            _ => assert_eq!(known, vec![assassin]),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_suspect_spectator() {
+    let mut config = Config::default();
+    config.anti_cheat.match_remote_addr = true;
+    let url = start_server_with_config(config);
+    let (game_info, _clients, key) = start_game(&url, NewGameCommand::default()).await;
+    assert!(key.iter().all(|x| x.character != Character::Unknown));
+
+    // every client connects from localhost just like the seated players.
+    let mut spectator = connect(&url, "Spectator").await;
+    spectator.join_game(&game_info.join_code).await.unwrap();
+    let state = spectator.wait_for_state(|_| true).await.unwrap();
+    assert!(state
+        .tiles
+        .iter()
+        .all(|x| x.character == Character::Unknown));
+}
+
+#[tokio::test]
+async fn test_metrics() {
+    let url = start_server();
+    let (_game_info, mut clients, _key) = start_game(&url, NewGameCommand::default()).await;
+    expect_code(
+        clients[0].request(Command::Rematch).await,
+        ErrorCode::GameNotOver,
+    );
+    let turn = clients[1]
+        .wait_for_state(|state| state.turn != Turn::Pregame)
+        .await
+        .unwrap()
+        .turn;
+
+    let (status, body) = http_request(&url, "GET", "/metrics", "", "").await;
+    assert_eq!(status, 200, "{}", body);
+    let value = |series: &str| -> f64 {
+        let line = body
+            .lines()
+            .find(|line| line.starts_with(series) && line[series.len()..].starts_with(' '))
+            .unwrap_or_else(|| panic!("missing {} in {}", series, body));
+        line[series.len() + 1..].parse().unwrap()
+    };
+
+    // every server has its own counters.
+    assert_eq!(value("webgame_connections"), 4.0);
+    assert_eq!(value("webgame_authenticated_players"), 4.0);
+    assert_eq!(
+        value("webgame_commands_received_total{cmd=\"mark_ready\"}"),
+        4.0
+    );
+    assert_eq!(
+        value("webgame_messages_sent_total{type=\"authenticated\"}"),
+        4.0
+    );
+    assert_eq!(value("webgame_errors_total{kind=\"bad_state\"}"), 1.0);
+    let turn_label = match turn.team() {
+        Some(Team::Red) => "red_spymaster_thinking",
+        _ => "blue_spymaster_thinking",
+    };
+    assert_eq!(
+        value(&format!(
+            "webgame_turn_transitions_total{{turn=\"{}\"}}",
+            turn_label
+        )),
+        1.0
+    );
+    assert_eq!(
+        value(&format!("webgame_games{{turn=\"{}\"}}", turn_label)),
+        1.0
+    );
+
+    // histogram buckets are cumulative and end with the total count.
+    let buckets: Vec<f64> = body
+        .lines()
+        .filter(|line| line.starts_with("webgame_serialization_seconds_bucket{"))
+        .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(buckets.len(), 8);
+    assert!(buckets.windows(2).all(|x| x[0] <= x[1]));
+    let count = value("webgame_serialization_seconds_count");
+    assert!(count > 0.0);
+    assert_eq!(
+        value("webgame_serialization_seconds_bucket{le=\"+Inf\"}"),
+        count
+    );
+    assert!(value("webgame_serialization_seconds_sum") > 0.0);
+}
+
+fn register(username: &str, password: Option<&str>, email: Option<&str>) -> Command {
+    Command::Register(RegisterCommand {
+        username: username.into(),
+        password: password.map(Into::into),
+        email: email.map(Into::into),
+    })
+}
+
+#[tokio::test]
+async fn test_accounts() {
+    let mut client = Client::connect(&start_server()).await.unwrap();
+    expect_code(
+        client
+            .request(register("alice", Some("hunter22"), None))
+            .await,
+        ErrorCode::AccountsDisabled,
+    );
+
+    let mail_dir = env::temp_dir().join(format!("webgame-mail-{}", Uuid::new_v4()));
+    let mut config = Config::default();
+    config.accounts.database = Some(":memory:".into());
+    config.accounts.mail_dir = mail_dir.clone();
+    let url = start_server_with_config(config);
+
+    let mut client = Client::connect_without_hello(&url).await.unwrap();
+    let hello = client
+        .hello(PROTOCOL_VERSION, Encoding::Json)
+        .await
+        .unwrap();
+    assert!(hello.features.iter().any(|x| x == "accounts"));
+    expect_code(
+        client.request(register("al", Some("hunter22"), None)).await,
+        ErrorCode::InvalidUsername,
+    );
+    expect_code(
+        client.request(register("alice", None, None)).await,
+        ErrorCode::PasswordOrEmailRequired,
+    );
+    expect_code(
+        client.request(register("alice", Some("short"), None)).await,
+        ErrorCode::PasswordTooShort,
+    );
+    client
+        .request(register(
+            "alice",
+            Some("correct horse"),
+            Some("alice@example.com"),
+        ))
+        .await
+        .unwrap();
+    expect_code(
+        client
+            .request(register("Alice", Some("battery staple"), None))
+            .await,
+        ErrorCode::UsernameTaken,
+    );
+
+    // a wrong password can be retried on the same connection
+    let mut client = Client::connect(&url).await.unwrap();
+    expect_code(
+        client
+            .sign_in(
+                "",
+                Credentials::Password {
+                    username: "alice".into(),
+                    password: "wrong password".into(),
+                },
+            )
+            .await,
+        ErrorCode::InvalidCredentials,
+    );
+    let player_info = client
+        .sign_in(
+            "Ali",
+            Credentials::Password {
+                username: "ALICE".into(),
+                password: "correct horse".into(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(player_info.nickname, "Ali");
+    assert_eq!(player_info.account.as_deref(), Some("alice"));
+    expect_code(
+        client
+            .request(register("bob", Some("hunter22"), None))
+            .await,
+        ErrorCode::AlreadyAuthenticated,
+    );
+
+    // the magic token is mailed to a file and the nickname is remembered
+    let mut client = Client::connect(&url).await.unwrap();
+    client
+        .request(Command::RequestMagicToken(RequestMagicTokenCommand {
+            username: "alice".into(),
+        }))
+        .await
+        .unwrap();
+    let mails: Vec<_> = fs::read_dir(&mail_dir)
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect();
+    assert_eq!(mails.len(), 1);
+    assert!(mails[0].starts_with("To: alice@example.com\n"));
+    let token = magic_token(&mails[0]);
+    let player_info = client
+        .sign_in(
+            "",
+            Credentials::MagicToken {
+                token: token.clone(),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(player_info.nickname, "Ali");
+    assert_eq!(player_info.account.as_deref(), Some("alice"));
+
+    // tokens can only be used once
+    let mut client = Client::connect(&url).await.unwrap();
+    expect_code(
+        client.sign_in("", Credentials::MagicToken { token }).await,
+        ErrorCode::InvalidCredentials,
+    );
+
+    // unknown usernames are rejected like wrong passwords and guessing
+    // is rate limited
+    let mut client = Client::connect(&url).await.unwrap();
+    let mut rejected = None;
+    for _ in 0..10 {
+        let credentials = Credentials::Password {
+            username: "mallory".into(),
+            password: "hunter22".into(),
+        };
+        match client.sign_in("", credentials).await {
+            Err(Error::Protocol(err)) if err.code() == ErrorCode::InvalidCredentials => {}
+            other => {
+                rejected = Some(other);
+                break;
+            }
+        }
+    }
+    expect_code(rejected.unwrap(), ErrorCode::RateLimited);
+
+    fs::remove_dir_all(&mail_dir).ok();
+}
+
+/// Extracts the sign-in token from a mail.
+fn magic_token(mail: &str) -> String {
+    mail.split("sign in: ")
+        .nth(1)
+        .and_then(|x| x.split_whitespace().next())
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_magic_token_nickname() {
+    let mail_dir = env::temp_dir().join(format!("webgame-mail-{}", Uuid::new_v4()));
+    let mut config = Config::default();
+    config.accounts.database = Some(":memory:".into());
+    config.accounts.mail_dir = mail_dir.clone();
+    config.moderation.blocklist = vec!["badword".into()];
+    let url = start_server_with_config(config);
+
+    let mut client = Client::connect(&url).await.unwrap();
+    client
+        .request(register("badword1", None, Some("carol@example.com")))
+        .await
+        .unwrap();
+    client
+        .request(Command::RequestMagicToken(RequestMagicTokenCommand {
+            username: "badword1".into(),
+        }))
+        .await
+        .unwrap();
+    let mail = fs::read_dir(&mail_dir)
+        .unwrap()
+        .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+        .next()
+        .unwrap();
+    let token = magic_token(&mail);
+    let credentials = || Credentials::MagicToken {
+        token: token.clone(),
+    };
+
+    // rejected nicknames do not use up the token, including the one
+    // taken from the username.
+    expect_code(
+        client.sign_in("Carol\u{200b}", credentials()).await,
+        ErrorCode::InvalidNicknameCharacters,
+    );
+    expect_code(
+        client.sign_in("", credentials()).await,
+        ErrorCode::NicknameNotAllowed,
+    );
+    let player_info = client.sign_in("Carol", credentials()).await.unwrap();
+    assert_eq!(player_info.nickname, "Carol");
+    assert_eq!(player_info.account.as_deref(), Some("badword1"));
+
+    fs::remove_dir_all(&mail_dir).ok();
+}
+
+/// Registers an account and signs in to it.
+async fn connect_account(url: &str, username: &str, encoding: Encoding) -> Client {
+    let mut client = Client::connect_with_encoding(url, encoding).await.unwrap();
+    client
+        .request(register(username, Some("correct horse"), None))
+        .await
+        .unwrap();
+    client
+        .sign_in(
+            username,
+            Credentials::Password {
+                username: username.into(),
+                password: "correct horse".into(),
+            },
+        )
+        .await
+        .unwrap();
+    client
+}
+
+/// Sends an HTTP request to the server and returns the status and body.
+async fn http_request(
+    url: &str,
+    method: &'static str,
+    path: &str,
+    headers: &str,
+    body: &str,
+) -> (u16, String) {
+    let addr = url
+        .trim_start_matches("ws://")
+        .trim_end_matches("/ws")
+        .to_string();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n{}\r\n{}",
+        method,
+        path,
+        addr,
+        body.len(),
+        headers,
+        body
+    );
+    let response = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response.split("\r\n\r\n").nth(1).unwrap_or("").to_string();
+    (status, body)
+}
+
+/// Fetches the leaderboard over HTTP.
+async fn http_leaderboard(url: &str) -> Vec<LeaderboardEntry> {
+    let (status, body) = http_request(url, "GET", "/api/leaderboard", "", "").await;
+    assert_eq!(status, 200, "{}", body);
+    serde_json::from_str(&body).unwrap()
+}
+
+/// Calls the admin API with a bearer token.
+async fn http_admin(
+    url: &str,
+    method: &'static str,
+    path: &str,
+    token: &str,
+    body: &str,
+) -> (u16, serde_json::Value) {
+    let headers = format!(
+        "Authorization: Bearer {}\r\nContent-Type: application/json\r\n",
+        token
+    );
+    let (status, body) = http_request(url, method, path, &headers, body).await;
+    (status, serde_json::from_str(&body).unwrap_or_default())
+}
+
+#[tokio::test]
+async fn test_admin() {
+    let (status, _) = http_admin(&start_server(), "GET", "/admin/games", "", "").await;
+    assert_eq!(status, 404, "admin API must not exist without a token");
+
+    let mut config = Config::default();
+    config.admin.token = Some("secret".into());
+    let url = start_server_with_config(config);
+    let mut client = connect(&url, "Alice").await;
+    let game_info = client.new_game(NewGameCommand::default()).await.unwrap();
+
+    for token in &["", "wrong"] {
+        let (status, body) = http_admin(&url, "GET", "/admin/games", token, "").await;
+        assert_eq!(status, 401);
+        assert_eq!(body["error"], "invalid admin token");
+    }
+    let (status, _) = http_admin(
+        &url,
+        "DELETE",
+        &format!("/admin/games/{}", game_info.join_code),
+        "wrong",
+        "",
+    )
+    .await;
+    assert_eq!(status, 401);
+    // the token is checked before the body is parsed.
+    let (status, body) = http_admin(&url, "POST", "/admin/notice", "wrong", "{").await;
+    assert_eq!(status, 401);
+    assert_eq!(body["error"], "invalid admin token");
+
+    let (status, games) = http_admin(&url, "GET", "/admin/games", "secret", "").await;
+    assert_eq!(status, 200);
+    assert_eq!(games.as_array().unwrap().len(), 1);
+    assert_eq!(games[0]["join_code"], game_info.join_code.as_str());
+    assert_eq!(games[0]["players"][0]["player"]["nickname"], "Alice");
+
+    let game_path = format!("/admin/games/{}", game_info.join_code);
+    let (status, game) = http_admin(&url, "GET", &game_path, "secret", "").await;
+    assert_eq!(status, 200);
+    assert_eq!(game["tiles"].as_array().unwrap().len(), 25);
+
+    let (status, _) = http_admin(
+        &url,
+        "POST",
+        "/admin/notice",
+        "secret",
+        r#"{"text": "Maintenance soon"}"#,
+    )
+    .await;
+    assert_eq!(status, 200);
+    let notice = client
+        .wait_for(|msg| match msg {
+            Message::ServerNotice(notice) => Some(notice.text),
+            _ => None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(notice, "Maintenance soon");
+
+    let (status, _) = http_admin(&url, "DELETE", &game_path, "secret", "").await;
+    assert_eq!(status, 200);
+    client
+        .wait_for(|msg| match msg {
+            Message::GameLeft => Some(()),
+            _ => None,
+        })
+        .await
+        .unwrap();
+    let (status, _) = http_admin(&url, "GET", &game_path, "secret", "").await;
+    assert_eq!(status, 404);
+    let (status, games) = http_admin(&url, "GET", "/admin/games", "secret", "").await;
+    assert_eq!(status, 200);
+    assert!(games.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_stats() {
+    check_stats(Encoding::Json).await;
+}
+
+#[tokio::test]
+async fn test_stats_msgpack() {
+    check_stats(Encoding::Msgpack).await;
+}
+
+/// Plays a game with accounts and checks the recorded statistics.
+async fn check_stats(encoding: Encoding) {
+    let mut config = Config::default();
+    config.accounts.database = Some(":memory:".into());
+    let url = start_server_with_config(config);
+
+    let mut red_spymaster = connect_account(&url, "redspy", encoding).await;
+    let game_info = red_spymaster
+        .new_game(NewGameCommand::default())
+        .await
+        .unwrap();
+    let mut red_operative = connect_account(&url, "redop", encoding).await;
+    let mut blue_spymaster = connect_account(&url, "bluespy", encoding).await;
+    let mut blue_operative = Client::connect_with_encoding(&url, encoding).await.unwrap();
+    blue_operative.authenticate("Guest").await.unwrap();
+    for client in [&mut red_operative, &mut blue_spymaster, &mut blue_operative].iter_mut() {
+        client.join_game(&game_info.join_code).await.unwrap();
+    }
+    take_seat(
+        &mut red_spymaster,
+        "redspy",
+        Team::Red,
+        PlayerRole::Spymaster,
+    )
+    .await;
+    take_seat(
+        &mut red_operative,
+        "redop",
+        Team::Red,
+        PlayerRole::Operative,
+    )
+    .await;
+    take_seat(
+        &mut blue_spymaster,
+        "bluespy",
+        Team::Blue,
+        PlayerRole::Spymaster,
+    )
+    .await;
+    take_seat(
+        &mut blue_operative,
+        "Guest",
+        Team::Blue,
+        PlayerRole::Operative,
+    )
+    .await;
+    for client in [
+        &mut red_spymaster,
+        &mut red_operative,
+        &mut blue_spymaster,
+        &mut blue_operative,
+    ]
+    .iter_mut()
+    {
+        client.send(Command::MarkReady).await.unwrap();
+    }
+
+    let state = red_spymaster
+        .wait_for_state(|state| state.turn != Turn::Pregame)
+        .await
+        .unwrap();
+    let key = state.tiles;
+    let starting_team = state.turn.team().unwrap();
+    let (spymaster, operative) = match starting_team {
+        Team::Red => (&mut red_spymaster, &mut red_operative),
+        Team::Blue => (&mut blue_spymaster, &mut blue_operative),
+    };
+
+    // one agent found for a clue of one, then the assassin.
+    spymaster
+        .send(Command::ShareCodename(ShareCodenameCommand {
+            codename: "zyzzyva".into(),
+            number: 1,
+            unlimited: false,
+        }))
+        .await
+        .unwrap();
+    operative
+        .wait_for_state(|state| state.clue.is_some())
+        .await
+        .unwrap();
+    let agent = find_tile(&key, starting_team.agent());
+    operative
+        .send(Command::RevealCard(RevealCardCommand { index: agent }))
+        .await
+        .unwrap();
+    operative
+        .wait_for_state(|state| state.tiles[agent].spotted)
+        .await
+        .unwrap();
+    let assassin = find_tile(&key, Character::Assassin);
+    operative
+        .send(Command::RevealCard(RevealCardCommand { index: assassin }))
+        .await
+        .unwrap();
+    operative
+        .wait_for_state(|state| state.turn == Turn::Endgame)
+        .await
+        .unwrap();
+
+    let red_won = (starting_team == Team::Blue) as u32;
+    let expected_red_spymaster = PlayerStats {
+        games_played_spymaster: 1,
+        games_won_spymaster: red_won,
+        clues_given: (starting_team == Team::Red) as u32,
+        clue_guesses: (starting_team == Team::Red) as u32 * 2,
+        successful_clues: (starting_team == Team::Red) as u32,
+        ..PlayerStats::default()
+    };
+    let stats = red_spymaster.stats().await.unwrap();
+    assert_eq!(stats.stats, Some(expected_red_spymaster));
+    let stats = red_operative.stats().await.unwrap();
+    assert_eq!(
+        stats.stats,
+        Some(PlayerStats {
+            games_played_operative: 1,
+            games_won_operative: red_won,
+            assassin_hits: (starting_team == Team::Red) as u32,
+            ..PlayerStats::default()
+        })
+    );
+
+    // guests have no stats of their own but can see the leaderboard.
+    let stats = blue_operative.stats().await.unwrap();
+    assert_eq!(stats.stats, None);
+    let usernames: Vec<&str> = stats
+        .leaderboard
+        .iter()
+        .map(|x| x.username.as_str())
+        .collect();
+    assert_eq!(usernames.len(), 3);
+    assert_eq!(usernames[0] == "bluespy", starting_team == Team::Red);
+    assert_eq!(http_leaderboard(&url).await, stats.leaderboard);
+}
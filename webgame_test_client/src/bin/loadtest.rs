@@ -0,0 +1,283 @@
+//! Plays many games against a local server to measure its latency.
+//!
+//! Every game is played by scripted players over their own websocket.  The
+//! spymasters share the key card with their operatives so that games end
+//! quickly and every command results in a new game state.
+use std::env;
+use std::net::IpAddr;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio_tungstenite::tungstenite::http::Uri;
+use webgame_protocol::{
+    Character, Command, GameStateSnapshot, NewGameCommand, PlayerAction, PlayerRole,
+    RevealCardCommand, SetPlayerTeamCommand, ShareCodenameCommand, Team, Tile, Turn,
+};
+use webgame_test_client::{Client, Error};
+
+struct Options {
+    url: String,
+    games: usize,
+    players: usize,
+}
+
+#[derive(Default)]
+struct Stats {
+    latencies: Vec<Duration>,
+    errors: usize,
+    games_completed: usize,
+}
+
+fn usage() -> ! {
+    eprintln!("usage: loadtest [--url URL] [--games N] [--players M]");
+    process::exit(1);
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        url: "ws://127.0.0.1:8002/ws".into(),
+        games: 10,
+        players: 4,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--url" => options.url = value,
+            "--games" => options.games = value.parse().unwrap_or_else(|_| usage()),
+            "--players" => options.players = value.parse().unwrap_or_else(|_| usage()),
+            _ => usage(),
+        }
+    }
+    if options.players < 4 {
+        eprintln!("a game needs at least 4 players");
+        process::exit(1);
+    }
+    options
+}
+
+/// Checks that the url points to this machine.
+///
+/// The load generator opens a lot of connections and is not meant to be
+/// pointed at servers other people are playing on.
+fn is_local_url(url: &str) -> bool {
+    let host = match url.parse::<Uri>() {
+        Ok(uri) => uri.host().unwrap_or("").to_string(),
+        Err(_) => return false,
+    };
+    match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(addr) => addr.is_loopback(),
+        Err(_) => host == "localhost",
+    }
+}
+
+struct Player {
+    client: Client,
+    nickname: String,
+    key: Arc<Mutex<Vec<Tile>>>,
+    stats: Arc<Mutex<Stats>>,
+}
+
+impl Player {
+    /// Picks the next command for the player or `None` if it's not their turn.
+    fn next_command(&self, state: &GameStateSnapshot) -> Option<Command> {
+        let me = state
+            .players
+            .iter()
+            .find(|x| x.player.nickname == self.nickname)?;
+        let team = me.team?;
+        match me.get_turn_player_action(state.turn)? {
+            PlayerAction::ShareCodename => Some(Command::ShareCodename(ShareCodenameCommand {
+                codename: "loadtest".into(),
+                number: 1,
+                unlimited: false,
+            })),
+            PlayerAction::Guess => {
+                // only the first operative of a team guesses.
+                let first_operative = state
+                    .players
+                    .iter()
+                    .find(|x| x.team == Some(team) && x.role == PlayerRole::Operative)?;
+                if first_operative.player.id != me.player.id {
+                    return None;
+                }
+                if state.clue.as_ref()?.guesses > 0 {
+                    return Some(Command::EndTurn);
+                }
+                let key = self.key.lock().unwrap();
+                let index = key.iter().enumerate().position(|(idx, tile)| {
+                    tile.character == team.agent() && !state.tiles[idx].spotted
+                })?;
+                Some(Command::RevealCard(RevealCardCommand { index }))
+            }
+        }
+    }
+
+    async fn play(mut self) -> Result<(), Error> {
+        let mut state = self.client.wait_for_state(|_| true).await?;
+        loop {
+            if state.turn == Turn::Endgame {
+                return Ok(());
+            }
+            if state.turn != Turn::Pregame {
+                let mut key = self.key.lock().unwrap();
+                if key.is_empty()
+                    && state
+                        .tiles
+                        .iter()
+                        .all(|x| x.character != Character::Unknown)
+                {
+                    *key = state.tiles.clone();
+                }
+            }
+
+            let cmd = match self.next_command(&state) {
+                Some(cmd) => cmd,
+                None => {
+                    state = self.client.wait_for_state(|_| true).await?;
+                    continue;
+                }
+            };
+
+            let sent_at = Instant::now();
+            self.client.send(cmd).await?;
+            match self.client.wait_for_state(|x| *x != state).await {
+                Ok(new_state) => {
+                    self.stats.lock().unwrap().latencies.push(sent_at.elapsed());
+                    state = new_state;
+                }
+                Err(Error::Protocol(err)) => {
+                    // most likely the chat rate limit, try again later.
+                    eprintln!("{}: {}", self.nickname, err.message());
+                    self.stats.lock().unwrap().errors += 1;
+                    tokio::time::delay_for(Duration::from_millis(500)).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+async fn run_game(
+    options: Arc<Options>,
+    game: usize,
+    stats: Arc<Mutex<Stats>>,
+) -> Result<(), Error> {
+    let key = Arc::new(Mutex::new(vec![]));
+    let mut players = vec![];
+    let mut join_code = None;
+
+    for idx in 0..options.players {
+        let nickname = format!("g{}p{}", game, idx);
+        let mut client = Client::connect(&options.url).await?;
+        client.set_timeout(Duration::from_secs(60));
+        client.authenticate(&nickname).await?;
+        match join_code {
+            None => {
+                join_code = Some(client.new_game(NewGameCommand::default()).await?.join_code);
+            }
+            Some(ref join_code) => {
+                client.join_game(join_code).await?;
+            }
+        }
+
+        // the first player of each team becomes the spymaster, so wait for
+        // the seat before the next player picks a team.
+        let team = if idx % 2 == 0 { Team::Red } else { Team::Blue };
+        client
+            .send(Command::SetPlayerTeam(SetPlayerTeamCommand {
+                team: Some(team),
+            }))
+            .await?;
+        client
+            .wait_for_state(|state| {
+                state
+                    .players
+                    .iter()
+                    .any(|x| x.player.nickname == nickname && x.team == Some(team))
+            })
+            .await?;
+
+        players.push(Player {
+            client,
+            nickname,
+            key: key.clone(),
+            stats: stats.clone(),
+        });
+    }
+
+    for player in players.iter_mut() {
+        player.client.send(Command::MarkReady).await?;
+    }
+
+    let tasks: Vec<_> = players
+        .into_iter()
+        .map(|player| tokio::spawn(player.play()))
+        .collect();
+    for task in tasks {
+        if let Ok(Err(err)) = task.await {
+            return Err(err);
+        }
+    }
+    stats.lock().unwrap().games_completed += 1;
+    Ok(())
+}
+
+fn percentile(sorted: &[Duration], pct: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::from_secs(0);
+    }
+    sorted[(sorted.len() - 1) * pct / 100]
+}
+
+#[tokio::main]
+async fn main() {
+    let options = Arc::new(parse_options());
+    if !is_local_url(&options.url) {
+        eprintln!(
+            "refusing to run against {}: only localhost is allowed",
+            options.url
+        );
+        process::exit(1);
+    }
+
+    println!(
+        "playing {} games with {} players each against {}",
+        options.games, options.players, options.url
+    );
+    let stats = Arc::new(Mutex::new(Stats::default()));
+    let started_at = Instant::now();
+    let games: Vec<_> = (0..options.games)
+        .map(|game| tokio::spawn(run_game(options.clone(), game, stats.clone())))
+        .collect();
+    for (game, task) in games.into_iter().enumerate() {
+        if let Ok(Err(err)) = task.await {
+            eprintln!("game {} failed: {}", game, err);
+        }
+    }
+    let elapsed = started_at.elapsed();
+
+    let mut stats = stats.lock().unwrap();
+    stats.latencies.sort();
+    println!(
+        "completed {}/{} games in {:.2?}",
+        stats.games_completed, options.games, elapsed
+    );
+    println!(
+        "{} commands, {} errors",
+        stats.latencies.len(),
+        stats.errors
+    );
+    println!(
+        "command to snapshot latency: p50 {:.2?}, p90 {:.2?}, p99 {:.2?}, max {:.2?}",
+        percentile(&stats.latencies, 50),
+        percentile(&stats.latencies, 90),
+        percentile(&stats.latencies, 99),
+        percentile(&stats.latencies, 100),
+    );
+}
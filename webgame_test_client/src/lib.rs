@@ -0,0 +1,298 @@
+//! A headless client for the webgame protocol.
+//!
+//! The client speaks the same websocket protocol as the browser client and
+//! is used to drive a server from tests.
+use std::fmt;
+use std::pin::Pin;
+use std::time::Duration;
+
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+use webgame_protocol::{
+    AuthenticateCommand, Command, CommandEnvelope, Credentials, Encoding, GameInfo,
+    GameStateSnapshot, HelloCommand, HelloMessage, JoinGameCommand, Message, NewGameCommand,
+    PlayerInfo, ProtocolError, StatsMessage, PROTOCOL_VERSION,
+};
+
+type WsSink = Pin<Box<dyn Sink<WsMessage, Error = WsError> + Send>>;
+type WsStream = Pin<Box<dyn Stream<Item = Result<WsMessage, WsError>> + Send>>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The websocket connection failed.
+    WebSocket(WsError),
+    /// The server sent something that is not a valid message.
+    Json(serde_json::Error),
+    /// A MessagePack frame could not be encoded or decoded.
+    Msgpack(String),
+    /// The server responded with an error.
+    Protocol(ProtocolError),
+    /// The server closed the connection.
+    Closed,
+    /// The server did not send the expected message in time.
+    Timeout,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::WebSocket(ref err) => write!(f, "websocket error: {}", err),
+            Error::Json(ref err) => write!(f, "invalid message: {}", err),
+            Error::Msgpack(ref err) => write!(f, "invalid msgpack message: {}", err),
+            Error::Protocol(ref err) => write!(f, "protocol error: {}", err.message()),
+            Error::Closed => write!(f, "connection closed"),
+            Error::Timeout => write!(f, "timed out"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<WsError> for Error {
+    fn from(err: WsError) -> Error {
+        Error::WebSocket(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Json(err)
+    }
+}
+
+pub struct Client {
+    sink: WsSink,
+    stream: WsStream,
+    encoding: Encoding,
+    timeout: Duration,
+    next_request_id: u64,
+}
+
+impl Client {
+    /// Connects to the websocket endpoint of a server and says hello.
+    pub async fn connect(url: &str) -> Result<Client, Error> {
+        Client::connect_with_encoding(url, Encoding::Json).await
+    }
+
+    /// Connects to a server and asks for the given encoding.
+    pub async fn connect_with_encoding(url: &str, encoding: Encoding) -> Result<Client, Error> {
+        let mut client = Client::connect_without_hello(url).await?;
+        client.hello(PROTOCOL_VERSION, encoding).await?;
+        Ok(client)
+    }
+
+    /// Connects to the websocket endpoint of a server without a handshake.
+    pub async fn connect_without_hello(url: &str) -> Result<Client, Error> {
+        let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+        let (sink, stream) = ws.split();
+        Ok(Client {
+            sink: Box::pin(sink),
+            stream: Box::pin(stream),
+            encoding: Encoding::Json,
+            timeout: Duration::from_secs(5),
+            next_request_id: 1,
+        })
+    }
+
+    /// Sets how long to wait for expected messages.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the encoding negotiated with the server.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Sends a command to the server.
+    pub async fn send(&mut self, cmd: Command) -> Result<(), Error> {
+        self.send_envelope(&cmd.into()).await
+    }
+
+    async fn send_envelope(&mut self, envelope: &CommandEnvelope) -> Result<(), Error> {
+        let msg = match self.encoding {
+            Encoding::Json => WsMessage::Text(serde_json::to_string(envelope)?),
+            Encoding::Msgpack => WsMessage::Binary(
+                rmp_serde::to_vec_named(envelope).map_err(|err| Error::Msgpack(err.to_string()))?,
+            ),
+        };
+        self.sink.send(msg).await?;
+        Ok(())
+    }
+
+    /// Sends a command with a request ID and waits until it was handled.
+    ///
+    /// Messages received before the acknowledgement are skipped.  Errors
+    /// for other commands are skipped as well.
+    pub async fn request(&mut self, cmd: Command) -> Result<(), Error> {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        self.send_envelope(&CommandEnvelope {
+            request_id: Some(request_id),
+            command: cmd,
+        })
+        .await?;
+
+        let timeout = self.timeout;
+        let wait = async {
+            loop {
+                match self.recv().await? {
+                    Message::Ack(ack) if ack.request_id == request_id => return Ok(()),
+                    Message::Error(err) if err.request_id() == Some(request_id) => {
+                        return Err(Error::Protocol(err))
+                    }
+                    _ => {}
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(rv) => rv,
+            Err(_) => Err(Error::Timeout),
+        }
+    }
+
+    /// Receives the next message from the server.
+    pub async fn recv(&mut self) -> Result<Message, Error> {
+        loop {
+            match self.stream.next().await {
+                Some(Ok(WsMessage::Text(text))) => return Ok(serde_json::from_str(&text)?),
+                Some(Ok(WsMessage::Binary(bytes))) => {
+                    return rmp_serde::from_read_ref(&bytes)
+                        .map_err(|err| Error::Msgpack(err.to_string()))
+                }
+                Some(Ok(WsMessage::Close(..))) | None => return Err(Error::Closed),
+                Some(Ok(..)) => continue,
+                Some(Err(err)) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Skips messages until the callback accepts one.
+    ///
+    /// Errors sent by the server are returned as `Error::Protocol`.
+    pub async fn wait_for<T, F>(&mut self, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(Message) -> Option<T>,
+    {
+        let timeout = self.timeout;
+        let wait = async {
+            loop {
+                match self.recv().await? {
+                    Message::Error(err) => return Err(Error::Protocol(err)),
+                    msg => {
+                        if let Some(rv) = f(msg) {
+                            return Ok(rv);
+                        }
+                    }
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, wait).await {
+            Ok(rv) => rv,
+            Err(_) => Err(Error::Timeout),
+        }
+    }
+
+    /// Waits for a game state snapshot matching a predicate.
+    pub async fn wait_for_state<F>(&mut self, mut f: F) -> Result<GameStateSnapshot, Error>
+    where
+        F: FnMut(&GameStateSnapshot) -> bool,
+    {
+        self.wait_for(|msg| match msg {
+            Message::GameStateSnapshot(snapshot) if f(&snapshot) => Some(snapshot),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Announces the protocol version and preferred encoding to the server.
+    pub async fn hello(
+        &mut self,
+        protocol_version: u32,
+        encoding: Encoding,
+    ) -> Result<HelloMessage, Error> {
+        self.send(Command::Hello(HelloCommand {
+            protocol_version,
+            client_name: concat!("webgame_test_client/", env!("CARGO_PKG_VERSION")).into(),
+            encodings: vec![encoding.name().into()],
+        }))
+        .await?;
+        let hello = self
+            .wait_for(|msg| match msg {
+                Message::Hello(hello) => Some(hello),
+                _ => None,
+            })
+            .await?;
+        self.encoding = hello.encoding;
+        Ok(hello)
+    }
+
+    /// Authenticates as a guest with the given nickname.
+    pub async fn authenticate(&mut self, nickname: &str) -> Result<PlayerInfo, Error> {
+        self.send(Command::Authenticate(AuthenticateCommand {
+            nickname: nickname.into(),
+            session_token: None,
+            credentials: None,
+        }))
+        .await?;
+        self.wait_for(|msg| match msg {
+            Message::Authenticated(player_info) => Some(player_info),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Signs in to an account.
+    ///
+    /// If the nickname is empty the one used last time is picked.
+    pub async fn sign_in(
+        &mut self,
+        nickname: &str,
+        credentials: Credentials,
+    ) -> Result<PlayerInfo, Error> {
+        self.send(Command::Authenticate(AuthenticateCommand {
+            nickname: nickname.into(),
+            session_token: None,
+            credentials: Some(credentials),
+        }))
+        .await?;
+        self.wait_for(|msg| match msg {
+            Message::Authenticated(player_info) => Some(player_info),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Creates a new game and joins it.
+    pub async fn new_game(&mut self, cmd: NewGameCommand) -> Result<GameInfo, Error> {
+        self.send(Command::NewGame(cmd)).await?;
+        self.wait_for_game_joined().await
+    }
+
+    /// Joins the game with the given join code.
+    pub async fn join_game(&mut self, join_code: &str) -> Result<GameInfo, Error> {
+        self.send(Command::JoinGame(JoinGameCommand {
+            join_code: join_code.into(),
+        }))
+        .await?;
+        self.wait_for_game_joined().await
+    }
+
+    /// Fetches the player's statistics and the leaderboard.
+    pub async fn stats(&mut self) -> Result<StatsMessage, Error> {
+        self.send(Command::GetStats).await?;
+        self.wait_for(|msg| match msg {
+            Message::Stats(stats) => Some(stats),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn wait_for_game_joined(&mut self) -> Result<GameInfo, Error> {
+        self.wait_for(|msg| match msg {
+            Message::GameJoined(game_info) => Some(game_info),
+            _ => None,
+        })
+        .await
+    }
+}